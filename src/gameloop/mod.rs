@@ -1,90 +1,450 @@
+pub mod replay;
+
+use super::input::action::{Action, Bindings};
+use super::input::key::Key;
+use super::input::script::{Recording, Script};
 use super::input::Input;
-use super::output::Output;
+use super::input::InputKey;
+use super::output::{Output, ReplayInfo};
+use super::scores::{HighScore, HighScores, MAX_NAME};
+use super::storage;
+use super::tris::game::MAX_PREVIEW;
+use super::tris::gravity::FRAMES_PER_SECOND;
+use super::tris::Clock;
+use super::tris::Command;
+use super::tris::Config;
+use super::tris::Game;
+use super::tris::GameEvent;
+use super::tris::LockReset;
+use super::tris::Mode;
+use super::tris::RandomizerKind;
+
+use rand;
+use std::path::Path;
 
 const GAME_LOOP_PERIOD: std::time::Duration = std::time::Duration::from_millis(10);
+// How many frames go by between redraws to keep the timers moving.
+const TIMER_FRAMES: u64 = 6;
+const QUIT_KEY: char = 'q';
+const RESUME_KEY: char = 'r';
+const RESTART_KEY: char = 'n';
+const REPLAY_SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const NORMAL_SPEED: usize = 2;
 
 pub struct GameLoop<'a> {
     input: &'a mut dyn Input,
     output: &'a mut dyn Output,
+    clock: &'a dyn Clock,
 }
 
-pub fn new<'a>(input: &'a mut dyn Input, output: &'a mut dyn Output) -> GameLoop<'a> {
+pub fn new<'a>(
+    input: &'a mut dyn Input,
+    output: &'a mut dyn Output,
+    clock: &'a dyn Clock,
+) -> GameLoop<'a> {
     GameLoop {
-        input: input,
-        output: output,
+        input,
+        output,
+        clock,
     }
 }
 
+// Starts a game with the config's seed, and picks a new seed for the next.
+fn new_game(config: &mut Config) -> Game {
+    let game = Game::new(config).unwrap();
+    config.seed = rand::random::<u64>();
+    game
+}
+
+// Where to go after a game has ended.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum AfterGame {
+    PlayAgain,
+    Menu,
+    Quit,
+}
+
+// The setting after the current one, going back round to the first.
+fn cycle<T: Copy + PartialEq>(all: &[T], current: T) -> T {
+    let i = all.iter().position(|v| *v == current).unwrap_or(0);
+    all[(i + 1) % all.len()]
+}
+
 impl<'a> GameLoop<'a> {
-    pub fn run(&mut self, width: isize, height: isize) {
-        self.play_game(width, height);
+    // Carries on with the saved game if there is one, and then goes to the
+    // main menu. Each game's keys are recorded to the given replay file.
+    // Only the first game uses the configured seed, and the rest get a new
+    // one each.
+    pub fn run(
+        &mut self,
+        config: &Config,
+        bindings: &Bindings,
+        saved: Option<Game>,
+        record: Option<&Path>,
+    ) {
+        let mut config = config.clone();
+        let mut next = saved;
+
+        'menu_loop: loop {
+            let mut g = match next.take() {
+                Some(game) => game,
+                None => {
+                    if !self.main_menu(&mut config) {
+                        break 'menu_loop;
+                    }
+                    new_game(&mut config)
+                }
+            };
+
+            while self.play(&mut g, bindings, record) {
+                let rank = self.high_score(&g);
+                match self.game_over(&g, rank) {
+                    AfterGame::PlayAgain => g = new_game(&mut config),
+                    AfterGame::Menu => continue 'menu_loop,
+                    AfterGame::Quit => break 'menu_loop,
+                }
+            }
+        }
 
         self.output.reset();
     }
 
-    fn play_game(&mut self, width: isize, height: isize) {
+    // Returns whether to start a new game, rather than quit.
+    fn main_menu(&mut self, config: &mut Config) -> bool {
+        loop {
+            self.output.show_main_menu(config);
+
+            match self.wait_char() {
+                'n' => return true,
+                'm' => {
+                    self.output.show_mode_menu(config.mode);
+                    let k = self.wait_char();
+                    if let Some(mode) = k
+                        .to_digit(10)
+                        .and_then(|i| Mode::ALL.get((i as usize).wrapping_sub(1)))
+                    {
+                        config.mode = *mode;
+                    }
+                }
+                's' => self.settings(config),
+                'h' => self.high_scores(config.mode),
+                QUIT_KEY => return false,
+                _ => (),
+            }
+        }
+    }
+
+    fn settings(&mut self, config: &mut Config) {
+        loop {
+            self.output.show_settings(config);
+
+            match self.wait_char() {
+                'p' => config.preview = config.preview % MAX_PREVIEW + 1,
+                'r' => config.randomizer = cycle(&RandomizerKind::ALL, config.randomizer),
+                'l' => config.lock_reset = cycle(&LockReset::ALL, config.lock_reset),
+                'b' | QUIT_KEY => return,
+                _ => (),
+            }
+        }
+    }
+
+    fn high_scores(&mut self, mut mode: Mode) {
+        let scores = match HighScores::load() {
+            Ok(scores) => scores,
+            Err(e) => {
+                self.report(Err(e));
+                HighScores::new()
+            }
+        };
+
+        loop {
+            self.output.show_high_scores(mode, &scores.table(mode));
+
+            match self.wait_char() {
+                'm' => mode = cycle(&Mode::ALL, mode),
+                'b' | QUIT_KEY => return,
+                _ => (),
+            }
+        }
+    }
+
+    // Asks for a name if the game made it into the high scores, and returns
+    // where it came.
+    fn high_score(&mut self, game: &Game) -> Option<usize> {
+        let mut scores = match HighScores::load() {
+            Ok(scores) => scores,
+            Err(e) => {
+                self.report(Err(e));
+                return None;
+            }
+        };
+        if !scores.qualifies(game) {
+            return None;
+        }
+
+        let name = self.prompt_name(game);
+        let date = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let rank = scores.add(HighScore::new(game, &name, date));
+        self.report(scores.save());
+        rank
+    }
+
+    fn prompt_name(&mut self, game: &Game) -> String {
+        let mut name = String::new();
+        self.drain_input();
+
+        loop {
+            self.output.show_name_prompt(game, &name);
+
+            let k = self.wait_key();
+            match (k.key, k.as_char()) {
+                (Key::Enter, _) => break,
+                (Key::Backspace, _) => {
+                    name.pop();
+                }
+                (_, Some(c)) if name.chars().count() < MAX_NAME => name.push(c),
+                _ => (),
+            }
+        }
+
+        match name.trim() {
+            "" => "Anonymous".to_string(),
+            name => name.to_string(),
+        }
+    }
+
+    fn game_over(&mut self, game: &Game, rank: Option<usize>) -> AfterGame {
+        self.drain_input();
+        self.output.show_game_over(game, rank);
+
+        loop {
+            match self.wait_char() {
+                'p' => return AfterGame::PlayAgain,
+                'm' => return AfterGame::Menu,
+                QUIT_KEY => return AfterGame::Quit,
+                _ => (),
+            }
+        }
+    }
+
+    fn wait_key(&mut self) -> InputKey {
+        loop {
+            if let Some(k) = self.input.get_key() {
+                return k;
+            }
+            std::thread::sleep(GAME_LOOP_PERIOD);
+        }
+    }
+
+    // Throws away keys left over from playing, so that they can't pick
+    // anything by mistake.
+    fn drain_input(&mut self) {
+        while self.input.get_key().is_some() {}
+    }
+
+    // Waits for a plain character, such as a menu choice.
+    fn wait_char(&mut self) -> char {
+        loop {
+            if let Some(c) = self.wait_key().as_char() {
+                return c;
+            }
+        }
+    }
+
+    // Plays a game, recording its keys to the replay file if there is one.
+    // Returns whether the game ended, rather than being quit.
+    fn play(&mut self, g: &mut Game, bindings: &Bindings, record: Option<&Path>) -> bool {
+        let mut recording = record.map(|_| Recording::new(g, bindings));
+
+        let ended = self.play_game(g, bindings, recording.as_mut());
+
+        if let (Some(path), Some(recording)) = (record, recording) {
+            let result = std::fs::write(path, recording.save())
+                .map_err(|e| format!("could not write {}: {}", path.display(), e));
+            self.report(result);
+        }
+
+        ended
+    }
+
+    // Plays back a recorded game, which can be paused, stepped through a
+    // frame at a time, sped up or slowed down, and sent to the piece with a
+    // given number.
+    pub fn replay(&mut self, game: Game, script: Script) {
+        let mut viewer = replay::Viewer::new(game, script);
+        let mut speed = NORMAL_SPEED;
+        let mut paused = false;
+        let mut entry = String::new();
         let mut changed = true;
-        let g = &mut super::tris::Game::new(width, height).unwrap();
+        let mut last = self.clock.now();
+        let mut due = 0.0;
 
-        'play_loop: loop {
+        'replay_loop: loop {
             if changed {
-                self.output.show_game(g);
+                let info = ReplayInfo {
+                    piece: viewer.piece(),
+                    speed: REPLAY_SPEEDS[speed],
+                    paused,
+                    ended: viewer.ended(),
+                    pressed: viewer.pressed(),
+                    entry: entry.clone(),
+                };
+                self.output.show_replay(viewer.game(), &info);
                 changed = false;
             }
 
-            if g.is_game_over() {
-                break 'play_loop;
+            while let Some(k) = self.input.get_key() {
+                changed = true;
+                match k.as_char() {
+                    Some(QUIT_KEY) => break 'replay_loop,
+                    Some('p') | Some(' ') => paused = !paused,
+                    Some('.') => {
+                        paused = true;
+                        viewer.step();
+                    }
+                    Some('+') => speed = (speed + 1).min(REPLAY_SPEEDS.len() - 1),
+                    Some('-') => speed = speed.saturating_sub(1),
+                    Some(c @ '0'..='9') => entry.push(c),
+                    Some('g') => {
+                        viewer.seek_piece(entry.parse::<usize>().unwrap_or(1).max(1));
+                        entry.clear();
+                        paused = true;
+                    }
+                    _ => entry.clear(),
+                }
             }
 
-            match self.input.get_key() {
-                None => {
-                    std::thread::sleep(GAME_LOOP_PERIOD);
-                    if g.tick() {
+            let now = self.clock.now();
+            if !paused && !viewer.ended() {
+                due += (now - last).as_secs_f64() * FRAMES_PER_SECOND as f64 * REPLAY_SPEEDS[speed];
+                while due >= 1.0 {
+                    due -= 1.0;
+                    if viewer.step() || viewer.game().get_frame().is_multiple_of(TIMER_FRAMES) {
                         changed = true;
                     }
                 }
-                Some(k) => match k {
-                    'a' => {
-                        if g.slide(-1) {
-                            changed = true;
+            } else {
+                due = 0.0;
+            }
+            last = now;
+
+            std::thread::sleep(GAME_LOOP_PERIOD);
+        }
+
+        self.output.reset();
+    }
+
+    // Steps the game through each frame as it comes due, reading the keys
+    // for it first and doing whatever they're bound to. Quitting saves the
+    // game so that it can be resumed, and finishing it throws the save away.
+    // Pausing brings up a menu to resume, restart or quit. Returns whether
+    // the game ended.
+    fn play_game(
+        &mut self,
+        g: &mut Game,
+        bindings: &Bindings,
+        mut recording: Option<&mut Recording>,
+    ) -> bool {
+        let mut changed = true;
+        let mut commands: Vec<Command> = vec![];
+
+        'play_loop: loop {
+            if changed {
+                if g.is_paused() {
+                    self.output.show_pause_menu(g);
+                } else {
+                    self.output.show_game(g);
+                }
+                changed = false;
+            }
+
+            if g.is_paused() {
+                if let Some(k) = self.input.get_key() {
+                    match k.as_char() {
+                        _ if bindings.action(k) == Some(Action::Pause) => g.resume(),
+                        Some(RESUME_KEY) => g.resume(),
+                        Some(RESTART_KEY) => {
+                            // Keys from the frame that was paused on belong
+                            // to the old game
+                            commands.clear();
+                            g.new_game();
+                            if let Some(recording) = recording.as_mut() {
+                                **recording = Recording::new(g, bindings);
+                            }
                         }
-                    }
-                    'd' => {
-                        if g.slide(1) {
-                            changed = true;
+                        Some(QUIT_KEY) => {
+                            self.report(storage::write(storage::SAVE_FILE, &g.save()));
+                            return false;
                         }
+                        _ => (),
                     }
-                    's' => {
-                        if g.down() {
-                            changed = true;
-                        } else {
-                            g.merge();
-                            // Check for lines
-                            g.random();
-                            // Merge and random
-                        }
+                    changed = true;
+                }
+
+                std::thread::sleep(GAME_LOOP_PERIOD);
+                continue;
+            }
+
+            let frames = g.frames_due(self.clock);
+            for _ in 0..frames {
+                let frame = g.get_frame();
+                self.input.frame(frame);
+
+                while let Some(k) = self.input.get_key() {
+                    let action = bindings.action(k);
+                    if action == Some(Action::Pause) {
+                        g.pause();
+                        changed = true;
+                        continue 'play_loop;
                     }
-                    'w' => {
-                        if g.rotate_clockwise() {
-                            changed = true;
-                        }
+
+                    // Keys that aren't bound do nothing, so they're left out
+                    if let (Some(recording), Some(_)) = (recording.as_mut(), action) {
+                        recording.push(frame, k);
                     }
-                    'W' => {
-                        if g.rotate_anticlockwise() {
-                            changed = true;
+
+                    match action {
+                        Some(Action::Quit) => {
+                            self.report(storage::write(storage::SAVE_FILE, &g.save()));
+                            return false;
                         }
+                        Some(action) => commands.extend(action.command()),
+                        None => (),
                     }
-                    ' ' => {
-                        g.drop();
-                        changed = true;
-                        g.merge();
-                        // Check for lines
-                        g.random();
+                }
+
+                g.step(&commands);
+                commands.clear();
+                if g.get_frame().is_multiple_of(TIMER_FRAMES) {
+                    changed = true;
+                }
+
+                while let Some(event) = g.poll_event() {
+                    changed = true;
+                    if event == GameEvent::TopOut || event == GameEvent::Finished {
+                        self.output.show_game(g);
+                        self.report(storage::remove(storage::SAVE_FILE));
+                        return true;
                     }
-                    'q' => break 'play_loop,
-                    _ => (),
-                },
+                }
             }
+
+            std::thread::sleep(GAME_LOOP_PERIOD);
+        }
+    }
+
+    // Shows what went wrong, if anything did, and waits for a key so that
+    // it isn't cleared away by the next screen.
+    fn report(&mut self, result: Result<(), String>) {
+        if let Err(e) = result {
+            self.output
+                .show_message(format!("\r\n{}\r\nPress any key\r\n", e));
+            self.drain_input();
+            self.wait_key();
         }
     }
 }
@@ -0,0 +1,175 @@
+use super::super::input::action::Action;
+use super::super::input::script::{KeyPress, Script};
+use super::super::input::{Input, InputKey};
+use super::super::tris::{Command, Game, GameEvent};
+
+// How many frames a key stays on show for after being pressed.
+const PRESSED_FRAMES: u64 = 20;
+
+// Plays a replay back a frame at a time, and can go back to any piece by
+// playing it again from the start.
+pub struct Viewer {
+    start: String,
+    script: Script,
+    game: Game,
+    input: Script,
+    piece: usize,
+    pressed: Vec<KeyPress>,
+    ended: bool,
+}
+
+impl Viewer {
+    pub fn new(game: Game, script: Script) -> Self {
+        Self {
+            start: game.save(),
+            input: script.clone(),
+            script,
+            game,
+            piece: 1,
+            pressed: vec![],
+            ended: false,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // The number of the piece being played, counting from 1.
+    pub fn piece(&self) -> usize {
+        self.piece
+    }
+
+    // Whether the recorded game is over, or the player quit.
+    pub fn ended(&self) -> bool {
+        self.ended
+    }
+
+    // The keys pressed in the last few frames.
+    pub fn pressed(&self) -> Vec<InputKey> {
+        let frame = self.game.get_frame();
+        self.pressed
+            .iter()
+            .filter(|p| p.frame + PRESSED_FRAMES > frame)
+            .map(|p| p.key)
+            .collect()
+    }
+
+    // Plays the next frame with the keys recorded for it. Returns whether
+    // anything happened that needs showing.
+    pub fn step(&mut self) -> bool {
+        if self.ended {
+            return false;
+        }
+
+        let frame = self.game.get_frame();
+        self.input.frame(frame);
+
+        let mut commands: Vec<Command> = vec![];
+        while let Some(key) = self.input.get_key() {
+            self.pressed.push(KeyPress { frame, key });
+            match self.script.bindings().action(key) {
+                Some(Action::Quit) => {
+                    self.ended = true;
+                    return true;
+                }
+                Some(action) => commands.extend(action.command()),
+                None => (),
+            }
+        }
+        self.pressed.retain(|p| p.frame + PRESSED_FRAMES > frame);
+
+        let mut changed = self.game.step(&commands);
+        while let Some(event) = self.game.poll_event() {
+            changed = true;
+            match event {
+                GameEvent::Locked(_) => self.piece += 1,
+                GameEvent::TopOut | GameEvent::Finished => self.ended = true,
+                _ => (),
+            }
+        }
+
+        changed
+    }
+
+    pub fn restart(&mut self) {
+        self.game = Game::load(&self.start).expect("could not reload the start of the replay");
+        self.input = self.script.clone();
+        self.piece = 1;
+        self.pressed.clear();
+        self.ended = false;
+    }
+
+    // Plays through to the frame the given piece comes in on, or as near as
+    // the replay goes.
+    pub fn seek_piece(&mut self, piece: usize) {
+        if piece < self.piece {
+            self.restart();
+        }
+
+        while self.piece < piece && !self.ended {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::input::action::Bindings;
+    use super::super::super::input::script::Recording;
+    use super::super::super::tris::Config;
+    use super::*;
+
+    fn viewer() -> Viewer {
+        let game = Game::new(&Config::default()).expect("could not create new game");
+        let mut r = Recording::new(&game, &Bindings::default());
+        for (frame, key) in [(1, 'a'), (1, ' '), (5, ' '), (40, ' '), (41, 'q')].iter() {
+            r.push(*frame, (*key).into());
+        }
+
+        let (game, script) = Script::load(&r.save()).expect("could not load replay");
+        Viewer::new(game, script)
+    }
+
+    #[test]
+    fn viewer_step() {
+        let mut v = viewer();
+
+        v.step();
+        assert_eq!(v.piece(), 1);
+        assert!(v.pressed().is_empty());
+
+        v.step();
+        assert_eq!(v.piece(), 2);
+        assert_eq!(v.pressed(), vec!['a'.into(), ' '.into()]);
+
+        while v.step() || !v.ended() {}
+        assert_eq!(v.piece(), 4);
+        assert_eq!(v.game().get_frame(), 41);
+        assert_eq!(v.pressed(), vec![' '.into(), 'q'.into()]);
+        assert!(!v.step());
+    }
+
+    #[test]
+    fn viewer_seek_piece() {
+        let mut v = viewer();
+
+        v.seek_piece(3);
+        assert_eq!(v.piece(), 3);
+        assert_eq!(v.game().get_frame(), 6);
+        let score = v.game().get_score();
+
+        v.seek_piece(10);
+        assert!(v.ended());
+        assert_eq!(v.piece(), 4);
+
+        v.seek_piece(3);
+        assert!(!v.ended());
+        assert_eq!(v.piece(), 3);
+        assert_eq!(v.game().get_frame(), 6);
+        assert_eq!(v.game().get_score(), score);
+
+        v.seek_piece(1);
+        assert_eq!(v.game().get_frame(), 0);
+    }
+}
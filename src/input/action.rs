@@ -0,0 +1,273 @@
+use super::super::storage;
+use super::super::tris::save::{SaveReader, SaveWriter};
+use super::super::tris::Command;
+use super::key::Key;
+use super::InputKey;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+pub const KEYS_FILE: &str = "keys.txt";
+
+// Something the player can do during a game, whichever key they do it with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateAnticlockwise,
+    Rotate180,
+    Hold,
+    Pause,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::RotateClockwise,
+        Action::RotateAnticlockwise,
+        Action::Rotate180,
+        Action::Hold,
+        Action::Pause,
+        Action::Quit,
+    ];
+
+    // What the action does to the game itself, if anything.
+    pub fn command(self) -> Option<Command> {
+        match self {
+            Action::MoveLeft => Some(Command::Left),
+            Action::MoveRight => Some(Command::Right),
+            Action::SoftDrop => Some(Command::SoftDrop),
+            Action::HardDrop => Some(Command::HardDrop),
+            Action::RotateClockwise => Some(Command::RotateClockwise),
+            Action::RotateAnticlockwise => Some(Command::RotateAnticlockwise),
+            Action::Rotate180 => Some(Command::Rotate180),
+            Action::Hold => Some(Command::Hold),
+            Action::Pause | Action::Quit => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::MoveLeft => write!(f, "move_left"),
+            Action::MoveRight => write!(f, "move_right"),
+            Action::SoftDrop => write!(f, "soft_drop"),
+            Action::HardDrop => write!(f, "hard_drop"),
+            Action::RotateClockwise => write!(f, "rotate_clockwise"),
+            Action::RotateAnticlockwise => write!(f, "rotate_anticlockwise"),
+            Action::Rotate180 => write!(f, "rotate_180"),
+            Action::Hold => write!(f, "hold"),
+            Action::Pause => write!(f, "pause"),
+            Action::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .iter()
+            .find(|action| action.to_string() == s)
+            .cloned()
+            .ok_or_else(|| format!("invalid action '{}'", s))
+    }
+}
+
+const DEFAULT_BINDINGS: [(Key, Action); 15] = [
+    (Key::Char('a'), Action::MoveLeft),
+    (Key::Left, Action::MoveLeft),
+    (Key::Char('d'), Action::MoveRight),
+    (Key::Right, Action::MoveRight),
+    (Key::Char('s'), Action::SoftDrop),
+    (Key::Down, Action::SoftDrop),
+    (Key::Char(' '), Action::HardDrop),
+    (Key::Char('w'), Action::RotateClockwise),
+    (Key::Up, Action::RotateClockwise),
+    (Key::Char('W'), Action::RotateAnticlockwise),
+    (Key::Char('x'), Action::Rotate180),
+    (Key::Char('c'), Action::Hold),
+    (Key::Char('p'), Action::Pause),
+    (Key::Esc, Action::Pause),
+    (Key::Char('q'), Action::Quit),
+];
+
+// A key and its action, saved as "action:key".
+struct Binding(Action, InputKey);
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.0, self.1)
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (action, key) = s
+            .split_once(':')
+            .ok_or_else(|| format!("invalid binding '{}'", s))?;
+
+        Ok(Binding(action.parse()?, key.parse()?))
+    }
+}
+
+// Which action each key does. A key does at most one thing, but an action
+// can have any number of keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    actions: HashMap<InputKey, Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            actions: DEFAULT_BINDINGS
+                .iter()
+                .map(|(key, action)| ((*key).into(), *action))
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn action(&self, key: InputKey) -> Option<Action> {
+        self.actions.get(&key).cloned()
+    }
+
+    // Gives an action just the given keys, taking them off anything else.
+    pub fn bind(&mut self, action: Action, keys: &[InputKey]) {
+        self.actions.retain(|_, a| *a != action);
+        for key in keys {
+            self.actions.insert(*key, action);
+        }
+    }
+
+    // Reads the player's bindings, one action per line followed by its keys:
+    // "hard_drop space k". Keys are named as in replays, such as "up",
+    // "f5" or "ctrl-d". Actions that aren't given keep their default keys,
+    // and lines starting with # are ignored.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut bindings = Self::default();
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let action = words.next().unwrap_or("").parse::<Action>()?;
+            let keys = words
+                .map(str::parse::<InputKey>)
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.bind(action, &keys);
+        }
+
+        Ok(bindings)
+    }
+
+    // Loads the bindings from the config directory, if they have been set.
+    pub fn load() -> Result<Self, String> {
+        match storage::read_config(KEYS_FILE)? {
+            Some(s) => Self::parse(&s).map_err(|e| format!("{} in {}", e, KEYS_FILE)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    // Replays keep the bindings they were recorded with.
+    pub fn save(&self, w: &mut SaveWriter) {
+        let mut bindings: Vec<Binding> = self
+            .actions
+            .iter()
+            .map(|(key, action)| Binding(*action, *key))
+            .collect();
+        bindings.sort_by_key(|b| b.1);
+        w.list("bindings", &bindings);
+    }
+
+    pub fn restore(r: &SaveReader) -> Result<Self, String> {
+        let actions = r
+            .list::<Binding>("bindings")?
+            .into_iter()
+            .map(|b| (b.1, b.0))
+            .collect();
+
+        Ok(Self { actions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_from_str() {
+        for action in Action::ALL.iter() {
+            assert_eq!(action.to_string().parse::<Action>(), Ok(*action));
+        }
+        assert!("jump".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn bindings_parse() {
+        let b = Bindings::parse(
+            "# Arrows are for wimps\n\
+             \n\
+             move_left h ctrl-h\n\
+             hard_drop space enter\n\
+             quit\n",
+        )
+        .expect("could not parse bindings");
+
+        let cases: Vec<(&str, Option<Action>)> = vec![
+            ("h", Some(Action::MoveLeft)),
+            ("ctrl-h", Some(Action::MoveLeft)),
+            ("a", None),
+            ("left", None),
+            ("space", Some(Action::HardDrop)),
+            ("enter", Some(Action::HardDrop)),
+            ("d", Some(Action::MoveRight)),
+            ("right", Some(Action::MoveRight)),
+            ("q", None),
+        ];
+
+        for case in cases {
+            let (key, want) = case;
+            let key = key.parse::<InputKey>().expect("invalid key");
+            assert_eq!(b.action(key), want, "key {}", key);
+        }
+    }
+
+    #[test]
+    fn bindings_parse_invalid() {
+        let cases = vec!["jump space", "hold ab", "hold \u{1}", "hold hyper-a"];
+
+        for case in cases {
+            assert!(Bindings::parse(case).is_err(), "parsing '{}'", case);
+        }
+    }
+
+    #[test]
+    fn bindings_save_restore() {
+        let mut b = Bindings::default();
+        b.bind(Action::Hold, &['c'.into(), ':'.into(), Key::F(5).into()]);
+
+        let mut w = SaveWriter::new();
+        b.save(&mut w);
+        let s = w.finish();
+        let r = SaveReader::new(&s).expect("could not read save");
+
+        assert_eq!(Bindings::restore(&r), Ok(b));
+    }
+}
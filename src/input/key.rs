@@ -0,0 +1,470 @@
+use std::fmt;
+use std::str::FromStr;
+
+const ESC: u8 = 0x1b;
+
+// A key on the keyboard. Printable keys are given as the character they
+// type, which already takes shift into account.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    F(u8),
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+// A key press, along with any modifiers held down with it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InputKey {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl From<Key> for InputKey {
+    fn from(key: Key) -> Self {
+        Self {
+            key,
+            modifiers: Modifiers::default(),
+        }
+    }
+}
+
+impl From<char> for InputKey {
+    fn from(c: char) -> Self {
+        Key::Char(c).into()
+    }
+}
+
+impl InputKey {
+    // The character typed, if nothing else was held down with it.
+    pub fn as_char(&self) -> Option<char> {
+        match self.key {
+            Key::Char(c) if self.modifiers == Modifiers::default() => Some(c),
+            _ => None,
+        }
+    }
+}
+
+const KEY_NAMES: [(Key, &str); 15] = [
+    (Key::Char(' '), "space"),
+    (Key::Enter, "enter"),
+    (Key::Tab, "tab"),
+    (Key::Backspace, "backspace"),
+    (Key::Esc, "escape"),
+    (Key::Up, "up"),
+    (Key::Down, "down"),
+    (Key::Left, "left"),
+    (Key::Right, "right"),
+    (Key::Home, "home"),
+    (Key::End, "end"),
+    (Key::PageUp, "pageup"),
+    (Key::PageDown, "pagedown"),
+    (Key::Insert, "insert"),
+    (Key::Delete, "delete"),
+];
+
+const MAX_FUNCTION_KEY: u8 = 20;
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self, KEY_NAMES.iter().find(|(k, _)| k == self)) {
+            (_, Some((_, name))) => write!(f, "{}", name),
+            (Key::F(n), _) => write!(f, "f{}", n),
+            (Key::Char(c), _) if c.is_whitespace() || c.is_control() => {
+                write!(f, "u+{:04x}", *c as u32)
+            }
+            (Key::Char(c), _) => write!(f, "{}", c),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+// Keys are written so that they can't contain whitespace: "ctrl-a", "up",
+// "space" or "W". Any other whitespace or control character is written as
+// its code point, such as "u+3000".
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((key, _)) = KEY_NAMES.iter().find(|(_, name)| *name == s) {
+            return Ok(*key);
+        }
+
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_whitespace() && !c.is_control() => return Ok(Key::Char(c)),
+            (Some('u'), Some('+')) => {
+                if let Some(c) = u32::from_str_radix(&s[2..], 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                {
+                    return Ok(Key::Char(c));
+                }
+            }
+            (Some('f'), Some(_)) => {
+                if let Ok(n) = s[1..].parse::<u8>() {
+                    if (1..=MAX_FUNCTION_KEY).contains(&n) {
+                        return Ok(Key::F(n));
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        Err(format!("invalid key '{}'", s))
+    }
+}
+
+impl fmt::Display for InputKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "shift-")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for InputKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::default();
+        let mut rest = s;
+
+        // A lone "-" is the minus key, rather than a modifier
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            match modifier {
+                "ctrl" => modifiers.ctrl = true,
+                "alt" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                _ => break,
+            }
+            rest = key;
+        }
+
+        let key = rest
+            .parse::<Key>()
+            .map_err(|_| format!("invalid key '{}'", s))?;
+        Ok(Self { key, modifiers })
+    }
+}
+
+// What the start of a run of bytes from the terminal comes to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Decoded {
+    // A key, and how many bytes it took up.
+    Key(InputKey, usize),
+    // Bytes for something that isn't a key, to be thrown away.
+    Skip(usize),
+    // The bytes stop part way through a key.
+    Incomplete,
+}
+
+// Turns the bytes from a terminal into keys: UTF-8 characters, control
+// characters, and the ANSI/VT escape sequences for the arrows, function
+// keys and the like. When no more bytes are on their way, anything left
+// unfinished is taken a byte at a time, which is how a lone Esc comes
+// through.
+pub fn decode(bytes: &[u8], more: bool) -> Decoded {
+    match decode_key(bytes) {
+        Decoded::Incomplete if !more && !bytes.is_empty() => match bytes[0] {
+            ESC => Decoded::Key(Key::Esc.into(), 1),
+            _ => Decoded::Key('\u{fffd}'.into(), 1),
+        },
+        decoded => decoded,
+    }
+}
+
+fn decode_key(bytes: &[u8]) -> Decoded {
+    let first = match bytes.first() {
+        Some(first) => *first,
+        None => return Decoded::Incomplete,
+    };
+
+    match first {
+        ESC => decode_escape(bytes),
+        b'\r' | b'\n' => Decoded::Key(Key::Enter.into(), 1),
+        b'\t' => Decoded::Key(Key::Tab.into(), 1),
+        0x08 | 0x7f => Decoded::Key(Key::Backspace.into(), 1),
+        0x01..=0x1a => {
+            let key = InputKey {
+                key: Key::Char((b'a' + first - 1) as char),
+                modifiers: Modifiers {
+                    ctrl: true,
+                    ..Modifiers::default()
+                },
+            };
+            Decoded::Key(key, 1)
+        }
+        0x00..=0x1f => Decoded::Skip(1),
+        _ => decode_utf8(bytes),
+    }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Decoded {
+    let len = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return Decoded::Key('\u{fffd}'.into(), 1),
+    };
+    if bytes.len() < len {
+        return Decoded::Incomplete;
+    }
+
+    match std::str::from_utf8(&bytes[..len])
+        .ok()
+        .and_then(|s| s.chars().next())
+    {
+        Some(c) => Decoded::Key(c.into(), len),
+        None => Decoded::Key('\u{fffd}'.into(), 1),
+    }
+}
+
+fn decode_escape(bytes: &[u8]) -> Decoded {
+    match bytes.get(1) {
+        None => Decoded::Incomplete,
+        Some(b'[') => decode_csi(bytes),
+        Some(b'O') => match bytes.get(2) {
+            None => Decoded::Incomplete,
+            Some(c) => match final_key(*c) {
+                Some(key) => Decoded::Key(key.into(), 3),
+                None => Decoded::Skip(3),
+            },
+        },
+        Some(&ESC) => Decoded::Key(Key::Esc.into(), 1),
+        // Alt is sent as Esc before the key
+        Some(_) => match decode_key(&bytes[1..]) {
+            Decoded::Key(mut key, len) => {
+                key.modifiers.alt = true;
+                Decoded::Key(key, len + 1)
+            }
+            Decoded::Skip(len) => Decoded::Skip(len + 1),
+            Decoded::Incomplete => Decoded::Incomplete,
+        },
+    }
+}
+
+// The keys that end a sequence with a letter: "ESC [ A" or "ESC O P".
+fn final_key(c: u8) -> Option<Key> {
+    match c {
+        b'A' => Some(Key::Up),
+        b'B' => Some(Key::Down),
+        b'C' => Some(Key::Right),
+        b'D' => Some(Key::Left),
+        b'H' => Some(Key::Home),
+        b'F' => Some(Key::End),
+        b'P' => Some(Key::F(1)),
+        b'Q' => Some(Key::F(2)),
+        b'R' => Some(Key::F(3)),
+        b'S' => Some(Key::F(4)),
+        _ => None,
+    }
+}
+
+// The keys that end a sequence with a number and a tilde: "ESC [ 15 ~".
+fn tilde_key(n: u32) -> Option<Key> {
+    match n {
+        1 | 7 => Some(Key::Home),
+        2 => Some(Key::Insert),
+        3 => Some(Key::Delete),
+        4 | 8 => Some(Key::End),
+        5 => Some(Key::PageUp),
+        6 => Some(Key::PageDown),
+        11..=15 => Some(Key::F((n - 10) as u8)),
+        17..=21 => Some(Key::F((n - 11) as u8)),
+        23..=26 => Some(Key::F((n - 12) as u8)),
+        28 | 29 => Some(Key::F((n - 13) as u8)),
+        31..=34 => Some(Key::F((n - 14) as u8)),
+        _ => None,
+    }
+}
+
+// A control sequence: ESC [, then numbers separated by semicolons, then a
+// final character. The second number, if any, gives the modifiers.
+fn decode_csi(bytes: &[u8]) -> Decoded {
+    let end = match bytes.iter().skip(2).position(|b| (0x40..=0x7e).contains(b)) {
+        Some(i) => i + 2,
+        None => return Decoded::Incomplete,
+    };
+    let len = end + 1;
+
+    let params: Vec<u32> = std::str::from_utf8(&bytes[2..end])
+        .unwrap_or("")
+        .split(';')
+        .map(|p| p.parse::<u32>().unwrap_or(1))
+        .collect();
+
+    let key = match bytes[end] {
+        b'~' => tilde_key(params[0]),
+        b'Z' => {
+            let key = InputKey {
+                key: Key::Tab,
+                modifiers: Modifiers {
+                    shift: true,
+                    ..Modifiers::default()
+                },
+            };
+            return Decoded::Key(key, len);
+        }
+        c => final_key(c),
+    };
+
+    match key {
+        Some(key) => {
+            let m = params.get(1).cloned().unwrap_or(1).saturating_sub(1);
+            let modifiers = Modifiers {
+                shift: m & 1 != 0,
+                alt: m & 2 != 0,
+                ctrl: m & 4 != 0,
+            };
+            Decoded::Key(InputKey { key, modifiers }, len)
+        }
+        None => Decoded::Skip(len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(key: Key, ctrl: bool, alt: bool, shift: bool) -> InputKey {
+        InputKey {
+            key,
+            modifiers: Modifiers { ctrl, alt, shift },
+        }
+    }
+
+    #[test]
+    fn key_decode() {
+        let cases: Vec<(&[u8], Decoded)> = vec![
+            (b"a", Decoded::Key('a'.into(), 1)),
+            (b"W", Decoded::Key('W'.into(), 1)),
+            (b"\r", Decoded::Key(Key::Enter.into(), 1)),
+            (b"\x7f", Decoded::Key(Key::Backspace.into(), 1)),
+            (
+                b"\x01",
+                Decoded::Key(with(Key::Char('a'), true, false, false), 1),
+            ),
+            ("é".as_bytes(), Decoded::Key('é'.into(), 2)),
+            ("€x".as_bytes(), Decoded::Key('€'.into(), 3)),
+            (&[0xe2, 0x82], Decoded::Incomplete),
+            (&[0xff], Decoded::Key('\u{fffd}'.into(), 1)),
+            (b"\x1b[A", Decoded::Key(Key::Up.into(), 3)),
+            (b"\x1b[Dq", Decoded::Key(Key::Left.into(), 3)),
+            (b"\x1bOP", Decoded::Key(Key::F(1).into(), 3)),
+            (b"\x1b[15~", Decoded::Key(Key::F(5).into(), 5)),
+            (b"\x1b[24~", Decoded::Key(Key::F(12).into(), 5)),
+            (b"\x1b[3~", Decoded::Key(Key::Delete.into(), 4)),
+            (
+                b"\x1b[1;5C",
+                Decoded::Key(with(Key::Right, true, false, false), 6),
+            ),
+            (
+                b"\x1b[1;4B",
+                Decoded::Key(with(Key::Down, false, true, true), 6),
+            ),
+            (
+                b"\x1b[Z",
+                Decoded::Key(with(Key::Tab, false, false, true), 3),
+            ),
+            (
+                b"\x1bx",
+                Decoded::Key(with(Key::Char('x'), false, true, false), 2),
+            ),
+            (b"\x1b\x1b", Decoded::Key(Key::Esc.into(), 1)),
+            (b"\x1b[99~", Decoded::Skip(5)),
+            (b"\x1b[1;5", Decoded::Incomplete),
+            (b"\x1b", Decoded::Incomplete),
+            (b"", Decoded::Incomplete),
+        ];
+
+        for case in cases {
+            let (bytes, want) = case;
+            assert_eq!(decode(bytes, true), want, "decoding {:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn key_decode_no_more() {
+        let cases: Vec<(&[u8], Decoded)> = vec![
+            (b"\x1b", Decoded::Key(Key::Esc.into(), 1)),
+            (b"\x1b[1;5", Decoded::Key(Key::Esc.into(), 1)),
+            (&[0xe2, 0x82], Decoded::Key('\u{fffd}'.into(), 1)),
+            (b"", Decoded::Incomplete),
+        ];
+
+        for case in cases {
+            let (bytes, want) = case;
+            assert_eq!(decode(bytes, false), want, "decoding {:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn key_from_str() {
+        let cases: Vec<(&str, Option<InputKey>)> = vec![
+            ("a", Some('a'.into())),
+            ("-", Some('-'.into())),
+            (":", Some(':'.into())),
+            ("space", Some(' '.into())),
+            ("up", Some(Key::Up.into())),
+            ("f", Some('f'.into())),
+            ("f12", Some(Key::F(12).into())),
+            ("ctrl-a", Some(with(Key::Char('a'), true, false, false))),
+            ("ctrl--", Some(with(Key::Char('-'), true, false, false))),
+            (
+                "ctrl-alt-shift-left",
+                Some(with(Key::Left, true, true, true)),
+            ),
+            ("u", Some('u'.into())),
+            ("u+3000", Some('\u{3000}'.into())),
+            ("u+00a0", Some('\u{a0}'.into())),
+            (
+                "alt-u+0085",
+                Some(with(Key::Char('\u{85}'), false, true, false)),
+            ),
+            ("u+d800", None),
+            ("u+", None),
+            ("f0", None),
+            ("ab", None),
+            ("meta-a", None),
+            ("\u{1}", None),
+            ("", None),
+        ];
+
+        for case in cases {
+            let (s, want) = case;
+            let got = s.parse::<InputKey>().ok();
+            assert_eq!(got, want, "parsing '{}'", s);
+            if let Some(key) = got {
+                assert_eq!(key.to_string(), s);
+            }
+        }
+    }
+}
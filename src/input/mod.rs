@@ -1,7 +1,14 @@
+pub mod action;
+pub mod key;
+pub mod script;
 pub mod stdin;
 
-pub type InputKey = char;
+pub use self::key::InputKey;
 
 pub trait Input {
     fn get_key(&mut self) -> Option<InputKey>;
+
+    // Says which frame any keys given next will be applied on, for inputs
+    // that are scripted.
+    fn frame(&mut self, _frame: u64) {}
 }
@@ -0,0 +1,204 @@
+use super::super::tris::save::{SaveReader, SaveWriter};
+use super::super::tris::Game;
+use super::action::Bindings;
+use super::Input;
+use super::InputKey;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+// A key, and the frame it was applied on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KeyPress {
+    pub frame: u64,
+    pub key: InputKey,
+}
+
+// Keys are written by name, so that spaces and the like don't need
+// escaping: "frame:key".
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.frame, self.key)
+    }
+}
+
+impl FromStr for KeyPress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key press '{}'", s);
+
+        let (frame, key) = s.split_once(':').ok_or_else(invalid)?;
+        let frame = frame.parse::<u64>().map_err(|_| invalid())?;
+        let key = key.parse::<InputKey>().map_err(|_| invalid())?;
+
+        Ok(Self { frame, key })
+    }
+}
+
+// The keys pressed during a game, along with the game as it was before any
+// of them and what the keys were bound to, so that it can be played back
+// exactly.
+pub struct Recording {
+    start: String,
+    bindings: Bindings,
+    keys: Vec<KeyPress>,
+}
+
+impl Recording {
+    pub fn new(game: &Game, bindings: &Bindings) -> Self {
+        Self {
+            start: game.save(),
+            bindings: bindings.clone(),
+            keys: vec![],
+        }
+    }
+
+    pub fn push(&mut self, frame: u64, key: InputKey) {
+        self.keys.push(KeyPress { frame, key });
+    }
+
+    // A replay is the save of the starting game with the bindings and keys
+    // added on.
+    pub fn save(&self) -> String {
+        let mut w = SaveWriter::append(self.start.clone());
+        self.bindings.save(&mut w);
+        w.list("keys", &self.keys);
+        w.finish()
+    }
+}
+
+// Gives out recorded keys on the frames they were pressed on.
+#[derive(Clone)]
+pub struct Script {
+    bindings: Bindings,
+    keys: VecDeque<KeyPress>,
+    frame: u64,
+}
+
+impl Script {
+    // Loads a replay, giving the game to play it back on.
+    pub fn load(s: &str) -> Result<(Game, Self), String> {
+        let game = Game::load(s)?;
+        let r = SaveReader::new(s)?;
+        let bindings = Bindings::restore(&r)?;
+        let keys = r.list("keys")?.into_iter().collect();
+
+        Ok((
+            game,
+            Self {
+                bindings,
+                keys,
+                frame: 0,
+            },
+        ))
+    }
+
+    // The bindings the keys were recorded with.
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+}
+
+impl Input for Script {
+    fn get_key(&mut self) -> Option<InputKey> {
+        match self.keys.front() {
+            Some(press) if press.frame <= self.frame => self.keys.pop_front().map(|p| p.key),
+            _ => None,
+        }
+    }
+
+    fn frame(&mut self, frame: u64) {
+        self.frame = frame;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::tris::Config;
+    use super::super::action::Action;
+    use super::super::key::Key;
+    use super::*;
+
+    #[test]
+    fn key_press_from_str() {
+        let cases: Vec<(&str, Option<KeyPress>)> = vec![
+            (
+                "12:a",
+                Some(KeyPress {
+                    frame: 12,
+                    key: 'a'.into(),
+                }),
+            ),
+            (
+                "0:space",
+                Some(KeyPress {
+                    frame: 0,
+                    key: ' '.into(),
+                }),
+            ),
+            (
+                "3::",
+                Some(KeyPress {
+                    frame: 3,
+                    key: ':'.into(),
+                }),
+            ),
+            (
+                "7:u+3000",
+                Some(KeyPress {
+                    frame: 7,
+                    key: '\u{3000}'.into(),
+                }),
+            ),
+            (
+                "5:ctrl-up",
+                Some(KeyPress {
+                    frame: 5,
+                    key: "ctrl-up".parse().unwrap(),
+                }),
+            ),
+            ("12", None),
+            ("x:a", None),
+            ("12:ab", None),
+            ("12:", None),
+        ];
+
+        for case in cases {
+            let (s, want) = case;
+            assert_eq!(s.parse::<KeyPress>().ok(), want, "parsing '{}'", s);
+        }
+    }
+
+    #[test]
+    fn script_replay() {
+        let game = Game::new(&Config::default()).expect("could not create new game");
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Quit, &['z'.into()]);
+        let mut r = Recording::new(&game, &bindings);
+        r.push(3, 'a'.into());
+        r.push(3, Key::Left.into());
+        r.push(5, '\u{3000}'.into());
+        r.push(10, 'z'.into());
+
+        let (loaded, mut script) = Script::load(&r.save()).expect("could not load replay");
+        assert_eq!(loaded.save(), game.save());
+        assert_eq!(script.bindings(), &bindings);
+
+        let mut keys = vec![];
+        for frame in 0..12 {
+            script.frame(frame);
+            while let Some(key) = script.get_key() {
+                keys.push((frame, key));
+            }
+        }
+        let want: Vec<(u64, InputKey)> = vec![
+            (3, 'a'.into()),
+            (3, Key::Left.into()),
+            (5, '\u{3000}'.into()),
+            (10, 'z'.into()),
+        ];
+        assert_eq!(keys, want);
+    }
+}
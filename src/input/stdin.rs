@@ -1,26 +1,110 @@
+use super::key::{decode, Decoded};
 use super::Input;
 use super::InputKey;
 
 use std::io::Read;
+use std::time::{Duration, Instant};
 use termion::AsyncReader;
 
-pub fn new<'a>() -> AsyncReader {
-    termion::async_stdin()
+// How long to wait for the rest of a key that has only partly come through,
+// before taking what's there as it is. A lone Esc is only known once this
+// has gone by.
+const KEY_TIMEOUT: Duration = Duration::from_millis(25);
+
+// Reads keys from the terminal without waiting, holding on to the bytes of
+// any key that hasn't all come through yet.
+pub struct Stdin<R> {
+    reader: R,
+    bytes: Vec<u8>,
+    // When the bytes held on to were found to stop part way through a key
+    waiting: Option<Instant>,
+}
+
+pub fn new() -> Stdin<AsyncReader> {
+    Stdin {
+        reader: termion::async_stdin(),
+        bytes: vec![],
+        waiting: None,
+    }
 }
 
-impl Input for AsyncReader {
+impl<R: Read> Input for Stdin<R> {
     fn get_key(&mut self) -> Option<InputKey> {
-        let mut buf: [u8; 1] = [0];
-        let res = self.read(&mut buf);
-        match res {
-            Ok(n) => {
-                if n == 0 {
-                    Option::<InputKey>::None
-                } else {
-                    Option::<InputKey>::Some(buf[0] as char)
+        let mut buf: [u8; 64] = [0; 64];
+        if let Ok(n) = self.reader.read(&mut buf) {
+            self.bytes.extend_from_slice(&buf[..n]);
+        }
+
+        loop {
+            let more = self
+                .waiting
+                .is_none_or(|since| since.elapsed() < KEY_TIMEOUT);
+
+            match decode(&self.bytes, more) {
+                Decoded::Key(key, len) => {
+                    self.bytes.drain(..len);
+                    self.waiting = None;
+                    return Some(key);
+                }
+                Decoded::Skip(len) => {
+                    self.bytes.drain(..len);
+                    self.waiting = None;
+                }
+                Decoded::Incomplete => {
+                    if !self.bytes.is_empty() && self.waiting.is_none() {
+                        self.waiting = Some(Instant::now());
+                    }
+                    return None;
                 }
             }
-            _ => Option::<InputKey>::None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::key::Key;
+    use super::*;
+
+    use std::collections::VecDeque;
+
+    // Gives out one chunk of bytes per read, as a terminal might.
+    struct Chunks(VecDeque<&'static [u8]>);
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let chunk = self.0.pop_front().unwrap_or(b"");
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    fn stdin(chunks: &[&'static [u8]]) -> Stdin<Chunks> {
+        Stdin {
+            reader: Chunks(chunks.iter().cloned().collect()),
+            bytes: vec![],
+            waiting: None,
+        }
+    }
+
+    #[test]
+    fn stdin_split_sequence() {
+        let mut s = stdin(&[b"a\x1b[", b"", b"A"]);
+
+        assert_eq!(s.get_key(), Some('a'.into()));
+        assert_eq!(s.get_key(), None);
+        assert_eq!(s.get_key(), Some(Key::Up.into()));
+        assert_eq!(s.get_key(), None);
+    }
+
+    #[test]
+    fn stdin_lone_escape() {
+        let mut s = stdin(&[b"\x1b"]);
+
+        assert_eq!(s.get_key(), None);
+        assert_eq!(s.get_key(), None);
+        std::thread::sleep(KEY_TIMEOUT);
+        assert_eq!(s.get_key(), Some(Key::Esc.into()));
+        assert_eq!(s.get_key(), None);
+    }
+}
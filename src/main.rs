@@ -1,14 +1,18 @@
 mod gameloop;
 mod input;
 mod output;
+mod scores;
+mod storage;
 mod tris;
 
+extern crate rand;
 extern crate structopt;
 extern crate termion;
 
+use std::path::PathBuf;
 use structopt::StructOpt;
 
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "textris", about = "A terminal-based tetris clone", version = VERSION)]
@@ -20,17 +24,162 @@ struct Opt {
     /// The height of the board
     #[structopt(short, long, default_value = "20")]
     height: isize,
+
+    /// The seed for the first game's piece sequence (random if omitted)
+    #[structopt(short, long)]
+    seed: Option<u64>,
+
+    /// How pieces are chosen: bag, history or memoryless
+    #[structopt(short, long, default_value = "bag")]
+    randomizer: tris::RandomizerKind,
+
+    /// The number of upcoming pieces to show (1-6)
+    #[structopt(short, long, default_value = "3")]
+    preview: usize,
+
+    /// Gravity per level in cells per frame, comma separated (eg. 0.02,0.5,1,20)
+    #[structopt(short, long)]
+    gravity: Option<tris::Gravity>,
+
+    /// The number of lines to clear to go up a level
+    #[structopt(short, long, default_value = "10")]
+    lines_per_level: usize,
+
+    /// The number of frames a landed piece waits before locking
+    #[structopt(long, default_value = "30")]
+    lock_delay: u32,
+
+    /// What restarts the lock delay: step (falling) or move (any movement)
+    #[structopt(long, default_value = "move")]
+    lock_reset: tris::LockReset,
+
+    /// The number of times moving can restart the lock delay on each row
+    #[structopt(long, default_value = "15")]
+    move_limit: usize,
+
+    /// The game mode: marathon, sprint, ultra, zen or dig
+    #[structopt(short, long, default_value = "marathon")]
+    mode: tris::Mode,
+
+    /// The number of garbage lines to clear in dig mode
+    #[structopt(long, default_value = "10")]
+    dig_lines: usize,
+
+    /// The percentage chance of each garbage row's hole moving (0-100)
+    #[structopt(long, default_value = "30")]
+    messiness: u32,
+
+    /// Carry on with the game saved when last quitting, if there is one
+    #[structopt(long)]
+    resume: bool,
+
+    /// Record the keys pressed to a replay file
+    #[structopt(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    subcommand: Option<Subcommand>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Subcommand {
+    /// Play back a recorded game
+    Replay {
+        /// The replay file, as written by --record
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
 }
 
-fn main() -> () {
+fn main() {
     let opt = Opt::from_args();
 
     println!("{:?}", opt);
 
+    if let Some(Subcommand::Replay { file }) = opt.subcommand {
+        replay(&file);
+        return;
+    }
+
+    let saved = if opt.resume { resume() } else { None };
+
+    let bindings = match input::action::Bindings::load() {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            eprintln!("could not load key bindings: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let config = tris::Config {
+        width: opt.width,
+        height: opt.height,
+        seed: opt.seed.unwrap_or_else(rand::random::<u64>),
+        randomizer: opt.randomizer,
+        preview: opt.preview,
+        gravity: opt.gravity.unwrap_or_default(),
+        lines_per_level: opt.lines_per_level,
+        lock_delay: opt.lock_delay,
+        lock_reset: opt.lock_reset,
+        move_limit: opt.move_limit,
+        mode: opt.mode,
+        dig_lines: opt.dig_lines,
+        messiness: opt.messiness,
+    };
+
+    // Catch bad options before the terminal goes into raw mode
+    if let Err(e) = tris::Game::new(&config) {
+        eprintln!("invalid options: {}", e);
+        std::process::exit(1);
+    }
+
+    let i = &mut input::stdin::new();
+    let o = &mut output::stdout::new();
+    let c = &tris::SystemClock::new();
+
+    let mut g = gameloop::new(i, o, c);
+
+    g.run(&config, &bindings, saved, opt.record.as_deref());
+}
+
+fn replay(file: &std::path::Path) {
+    let loaded = std::fs::read_to_string(file)
+        .map_err(|e| format!("could not read {}: {}", file.display(), e))
+        .and_then(|s| input::script::Script::load(&s));
+    let (game, script) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("could not load replay: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let i = &mut input::stdin::new();
     let o = &mut output::stdout::new();
+    let c = &tris::SystemClock::new();
+
+    let mut g = gameloop::new(i, o, c);
+
+    g.replay(game, script);
+}
 
-    let mut g = gameloop::new(i, o);
+// Loads the saved game, leaving it be if it can't be read so that it isn't
+// overwritten on quitting.
+fn resume() -> Option<tris::Game> {
+    let saved = match storage::read(storage::SAVE_FILE) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    g.run(opt.width, opt.height);
+    match saved.map(|s| tris::Game::load(&s)) {
+        Some(Ok(game)) if !game.is_game_over() => Some(game),
+        Some(Err(e)) => {
+            eprintln!("could not resume saved game: {}", e);
+            std::process::exit(1);
+        }
+        _ => None,
+    }
 }
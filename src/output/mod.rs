@@ -1,10 +1,32 @@
 pub mod stdout;
 
+pub use super::input::InputKey;
+pub use super::scores::HighScore;
+pub use super::tris::Config;
 pub use super::tris::Game;
+pub use super::tris::Mode;
+
+// Where a replay is up to, and how it's being played.
+pub struct ReplayInfo {
+    pub piece: usize,
+    pub speed: f64,
+    pub paused: bool,
+    pub ended: bool,
+    pub pressed: Vec<InputKey>,
+    // A piece number being typed in
+    pub entry: String,
+}
 
 pub trait Output {
     fn reset(&mut self);
-    fn show_main_menu(&mut self);
+    fn show_main_menu(&mut self, config: &Config);
+    fn show_mode_menu(&mut self, current: Mode);
+    fn show_settings(&mut self, config: &Config);
     fn show_message(&mut self, message: String);
     fn show_game(&mut self, game: &Game);
+    fn show_pause_menu(&mut self, game: &Game);
+    fn show_name_prompt(&mut self, game: &Game, name: &str);
+    fn show_game_over(&mut self, game: &Game, rank: Option<usize>);
+    fn show_high_scores(&mut self, mode: Mode, scores: &[&HighScore]);
+    fn show_replay(&mut self, game: &Game, info: &ReplayInfo);
 }
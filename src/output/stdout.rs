@@ -1,14 +1,27 @@
+use super::super::tris::gravity::FRAMES_PER_SECOND;
+use super::super::tris::Block;
+use super::super::tris::ClearKind;
 use super::super::tris::Colour;
+use super::super::tris::PieceKind;
+use super::Config;
 use super::Game;
+use super::HighScore;
+use super::Mode;
 use super::Output;
+use super::ReplayInfo;
 
 use std::io::Write;
 use termion::color;
 use termion::raw::IntoRawMode;
 
-const BLOCK: &'static str = "\u{259A}";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const BLOCK: &str = "\u{259A}";
+const GHOST: &str = "\u{2591}";
+const PANEL_GAP: &str = "  ";
+const PANEL_BLOCK_WIDTH: isize = 4;
+const PANEL_BLOCK_HEIGHT: isize = 2;
 
-pub fn new<'a>() -> termion::raw::RawTerminal<std::io::Stdout> {
+pub fn new() -> termion::raw::RawTerminal<std::io::Stdout> {
     let mut stdout = std::io::stdout().into_raw_mode().unwrap();
 
     write!(stdout, "{}", termion::cursor::Hide).unwrap();
@@ -61,30 +74,210 @@ fn block_style(i: usize) -> String {
     }
 }
 
-impl<'a> Output for termion::raw::RawTerminal<std::io::Stdout> {
+fn ghost_style(i: usize) -> String {
+    format!(
+        "{}{}",
+        color::Fg(match i {
+            0 => color::Rgb(255, 128, 128),
+            1 => color::Rgb(255, 255, 128),
+            2 => color::Rgb(128, 255, 128),
+            3 => color::Rgb(128, 255, 255),
+            4 => color::Rgb(128, 128, 255),
+            5 => color::Rgb(255, 128, 255),
+            6 => color::Rgb(226, 196, 128),
+            _ => color::Rgb(255, 255, 255),
+        }),
+        color::Bg(color::Rgb(0, 0, 0))
+    )
+}
+
+fn block_row(block: &Block, y: isize) -> String {
+    let mut row = String::new();
+
+    for x in 0..PANEL_BLOCK_WIDTH {
+        match block.colour() {
+            Colour::Value(i) if block.get(x, y) => row += &format!("{}{}", block_style(i), BLOCK),
+            _ => row += &format!("{} ", color::Bg(color::Rgb(0, 0, 0))),
+        }
+    }
+
+    row
+}
+
+// Formats a number of frames as m:ss.cc
+fn time(frames: u64) -> String {
+    let centis = frames * 100 / FRAMES_PER_SECOND;
+    format!(
+        "{}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+// What the player needs to know about how far through the mode they are.
+fn mode_info(game: &Game) -> String {
+    let mode = game.get_mode();
+    let frame = game.get_frame();
+    let lines_left = mode
+        .line_goal()
+        .map(|goal| goal.saturating_sub(game.get_lines()))
+        .unwrap_or(0);
+
+    match mode {
+        Mode::Marathon => format!("Lines left: {}", lines_left),
+        Mode::Sprint => format!("Time: {} Lines left: {}", time(frame), lines_left),
+        Mode::Ultra => format!(
+            "Time left: {}",
+            time(mode.time_limit().unwrap_or(0).saturating_sub(frame))
+        ),
+        Mode::Zen => format!("Time: {}", time(frame)),
+        Mode::Dig => format!(
+            "Time: {} Garbage left: {}",
+            time(frame),
+            game.get_garbage_left()
+        ),
+    }
+}
+
+// How the player is doing: rates, combos, droughts, and what they've placed
+// and cleared.
+fn stats_info(game: &Game) -> Vec<String> {
+    let stats = game.get_stats();
+    let frame = game.get_frame();
+
+    let pieces: Vec<String> = PieceKind::ALL
+        .iter()
+        .map(|kind| format!("{}{}", kind, stats.pieces(*kind)))
+        .collect();
+    let clears: Vec<String> = ClearKind::ALL
+        .iter()
+        .map(|clear| format!("{} {}", clear, stats.clears(*clear)))
+        .collect();
+
+    vec![
+        format!("PPS: {:.2} APM: {:.0}", stats.pps(frame), stats.apm(frame)),
+        format!("Keys/piece: {:.2}", stats.keys_per_piece()),
+        format!("Max combo: {}", stats.max_combo()),
+        format!(
+            "I drought: {} (max {})",
+            stats.drought(),
+            stats.max_drought()
+        ),
+        pieces.join(" "),
+        clears[..2].join(" "),
+        clears[2..].join(" "),
+        format!("T-spins {}", stats.tspins()),
+    ]
+}
+
+fn clear_screen(out: &mut dyn Write) {
+    write!(
+        out,
+        "{}{}{}{}",
+        termion::clear::All,
+        color::Fg(color::Rgb(196, 196, 196)),
+        color::Bg(color::Rgb(0, 0, 0)),
+        termion::cursor::Goto(1, 1),
+    )
+    .unwrap();
+}
+
+fn panel_label(label: &str) -> String {
+    format!(
+        "{}{}{}",
+        color::Fg(color::Rgb(128, 128, 128)),
+        color::Bg(color::Rgb(0, 0, 0)),
+        label
+    )
+}
+
+// The panel beside the board, one entry per board row. The pieces are kept
+// hidden while paused.
+fn side_panel(game: &Game) -> Vec<String> {
+    let mut lines = vec![panel_label("Hold:")];
+
+    match game.get_hold() {
+        Some(kind) if !game.is_paused() => {
+            let block = Block::with_kind(kind);
+            for y in 0..PANEL_BLOCK_HEIGHT {
+                lines.push(block_row(&block, y));
+            }
+        }
+        _ => {
+            for _ in 0..PANEL_BLOCK_HEIGHT {
+                lines.push(String::new());
+            }
+        }
+    }
+    lines.push(String::new());
+
+    lines.push(panel_label("Next:"));
+
+    for kind in game.get_preview() {
+        let block = Block::with_kind(kind);
+        for y in 0..PANEL_BLOCK_HEIGHT {
+            if game.is_paused() {
+                lines.push(String::new());
+            } else {
+                lines.push(block_row(&block, y));
+            }
+        }
+        lines.push(String::new());
+    }
+
+    lines.push(panel_label("Stats:"));
+    for line in stats_info(game) {
+        lines.push(format!("{}{}", color::Fg(color::Rgb(196, 196, 196)), line));
+    }
+
+    lines
+}
+
+impl Output for termion::raw::RawTerminal<std::io::Stdout> {
     fn reset(&mut self) {
         write!(self, "{}{}", termion::cursor::Show, termion::style::Reset,).unwrap();
     }
 
-    fn show_main_menu(&mut self) {
-        write!(
-            self,
-            "{}{}{}",
-            termion::clear::All,
-            termion::color::Fg(termion::color::Rgb(196, 196, 196)),
-            termion::cursor::Goto(1, 1),
-        )
-        .unwrap();
+    fn show_main_menu(&mut self, config: &Config) {
+        clear_screen(self);
 
-        //write!(self, "textris-{}\r\n", VERSION).unwrap();
+        write!(self, "textris-{}\r\n", VERSION).unwrap();
         write!(self, "\r\n").unwrap();
         write!(self, "Menu:\r\n").unwrap();
         write!(self, "n. New game\r\n").unwrap();
+        write!(self, "m. Mode: {}\r\n", config.mode).unwrap();
+        write!(self, "s. Settings\r\n").unwrap();
+        write!(self, "h. High scores\r\n").unwrap();
         write!(self, "q. Quit\r\n").unwrap();
         write!(self, "> ").unwrap();
         self.flush().unwrap();
     }
 
+    fn show_mode_menu(&mut self, current: Mode) {
+        clear_screen(self);
+
+        write!(self, "Mode:\r\n").unwrap();
+        for (i, mode) in Mode::ALL.iter().enumerate() {
+            let marker = if *mode == current { "*" } else { " " };
+            write!(self, "{}. {}{}\r\n", i + 1, mode, marker).unwrap();
+        }
+        write!(self, "> ").unwrap();
+        self.flush().unwrap();
+    }
+
+    fn show_settings(&mut self, config: &Config) {
+        clear_screen(self);
+
+        write!(self, "Settings:\r\n").unwrap();
+        write!(self, "p. Preview: {}\r\n", config.preview).unwrap();
+        write!(self, "r. Randomizer: {}\r\n", config.randomizer).unwrap();
+        write!(self, "l. Lock reset: {}\r\n", config.lock_reset).unwrap();
+        write!(self, "b. Back\r\n").unwrap();
+        write!(self, "> ").unwrap();
+        self.flush().unwrap();
+    }
+
     fn show_message(&mut self, message: String) {
         write!(self, "{}", message).unwrap();
         self.flush().unwrap();
@@ -116,13 +309,57 @@ impl<'a> Output for termion::raw::RawTerminal<std::io::Stdout> {
 
         write!(
             self,
-            "{}Score: {}{}\r\n\r\n",
+            "{}Score: {}{}\r\n",
             color::Fg(color::Rgb(128, 128, 128)),
             color::Fg(color::Rgb(255, 196, 196)),
             game.get_score()
         )
         .unwrap();
 
+        match game.get_last_score() {
+            Some(event) => write!(
+                self,
+                "{}{}\r\n",
+                color::Fg(color::Rgb(255, 255, 196)),
+                event
+            )
+            .unwrap(),
+            None => write!(self, "\r\n").unwrap(),
+        }
+
+        write!(
+            self,
+            "{}Level: {}{} {}Lines: {}{}\r\n",
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(196, 255, 196)),
+            game.get_level(),
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(196, 196, 255)),
+            game.get_lines()
+        )
+        .unwrap();
+
+        write!(
+            self,
+            "{}{}: {}{}\r\n",
+            color::Fg(color::Rgb(128, 128, 128)),
+            game.get_mode(),
+            color::Fg(color::Rgb(196, 255, 255)),
+            mode_info(game)
+        )
+        .unwrap();
+
+        write!(
+            self,
+            "{}Seed: {}{}\r\n\r\n",
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(196, 196, 196)),
+            game.get_seed()
+        )
+        .unwrap();
+
+        let side = side_panel(game);
+
         for y in 0..height {
             // Display left wall
             write!(
@@ -134,9 +371,14 @@ impl<'a> Output for termion::raw::RawTerminal<std::io::Stdout> {
             )
             .unwrap();
 
-            // Display contents
+            // Display contents, hiding the board while paused
             for x in 0..width {
-                match game.display_get(x, y) {
+                let colour = if game.is_paused() {
+                    Colour::Empty
+                } else {
+                    game.display_get(x, y)
+                };
+                match colour {
                     Colour::Empty => write!(
                         self,
                         "{}{}{}",
@@ -147,18 +389,36 @@ impl<'a> Output for termion::raw::RawTerminal<std::io::Stdout> {
                     .unwrap(),
 
                     Colour::Value(i) => write!(self, "{}{}", block_style(i), BLOCK).unwrap(),
+
+                    Colour::Ghost(i) => write!(self, "{}{}", ghost_style(i), GHOST).unwrap(),
+
+                    Colour::Garbage => write!(
+                        self,
+                        "{}{}{}",
+                        color::Fg(color::Rgb(160, 160, 160)),
+                        color::Bg(color::Rgb(112, 112, 112)),
+                        BLOCK
+                    )
+                    .unwrap(),
                 }
             }
 
             // Display right wall
             write!(
                 self,
-                "{}{}{}\r\n",
+                "{}{}{}",
                 color::Fg(color::Rgb(128, 128, 128)),
                 color::Bg(color::Rgb(96, 96, 96)),
                 BLOCK
             )
             .unwrap();
+
+            // Display side panel
+            write!(self, "{}{}", color::Bg(color::Rgb(0, 0, 0)), PANEL_GAP).unwrap();
+            if let Some(line) = side.get(y as usize) {
+                write!(self, "{}", line).unwrap();
+            }
+            write!(self, "\r\n").unwrap();
         }
 
         //Display bottom wall
@@ -174,7 +434,28 @@ impl<'a> Output for termion::raw::RawTerminal<std::io::Stdout> {
             write!(self, "{}", BLOCK).unwrap();
         }
 
-        if game.is_game_over() {
+        // Display what is left of the side panel below the board
+        for line in side.iter().skip(height as usize) {
+            write!(
+                self,
+                "\r\n{}{}{}{}",
+                color::Bg(color::Rgb(0, 0, 0)),
+                " ".repeat((width + 2) as usize),
+                PANEL_GAP,
+                line
+            )
+            .unwrap();
+        }
+
+        if game.is_finished() {
+            write!(
+                self,
+                "\r\n\r\n{}{}Finished\r\n",
+                color::Fg(color::Rgb(196, 255, 196)),
+                color::Bg(color::Rgb(0, 0, 0))
+            )
+            .unwrap();
+        } else if game.is_game_over() {
             write!(
                 self,
                 "\r\n\r\n{}{}Game Over\r\n",
@@ -188,4 +469,145 @@ impl<'a> Output for termion::raw::RawTerminal<std::io::Stdout> {
 
         self.flush().unwrap();
     }
+
+    fn show_replay(&mut self, game: &Game, info: &ReplayInfo) {
+        self.show_game(game);
+
+        let state = if info.ended {
+            "Ended"
+        } else if info.paused {
+            "Paused"
+        } else {
+            "Playing"
+        };
+        let pressed: Vec<String> = info.pressed.iter().map(|k| k.to_string()).collect();
+
+        write!(
+            self,
+            "\r\n\r\n{}Replay: {}{} {}x {}Piece: {}{} {}Frame: {}{}\r\n",
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(196, 255, 255)),
+            state,
+            info.speed,
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(196, 196, 255)),
+            info.piece,
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(196, 196, 196)),
+            game.get_frame()
+        )
+        .unwrap();
+        write!(
+            self,
+            "{}Input: {}{}\r\n",
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(255, 255, 196)),
+            pressed.join(" ")
+        )
+        .unwrap();
+        write!(
+            self,
+            "{}p pause  . step  +/- speed  <n>g go to piece {}{}{}  q quit\r\n",
+            color::Fg(color::Rgb(128, 128, 128)),
+            color::Fg(color::Rgb(196, 196, 255)),
+            info.entry,
+            color::Fg(color::Rgb(128, 128, 128)),
+        )
+        .unwrap();
+
+        self.flush().unwrap();
+    }
+
+    fn show_pause_menu(&mut self, game: &Game) {
+        self.show_game(game);
+
+        write!(
+            self,
+            "\r\n\r\n{}Paused\r\n{}r. Resume\r\nn. Restart\r\nq. Quit\r\n",
+            color::Fg(color::Rgb(196, 255, 255)),
+            color::Fg(color::Rgb(196, 196, 196)),
+        )
+        .unwrap();
+
+        self.flush().unwrap();
+    }
+
+    fn show_name_prompt(&mut self, game: &Game, name: &str) {
+        self.show_game(game);
+
+        write!(
+            self,
+            "{}New high score! Name: {}{}_\r\n",
+            color::Fg(color::Rgb(196, 196, 196)),
+            color::Fg(color::Rgb(255, 255, 196)),
+            name
+        )
+        .unwrap();
+
+        self.flush().unwrap();
+    }
+
+    fn show_game_over(&mut self, game: &Game, rank: Option<usize>) {
+        self.show_game(game);
+
+        if let Some(rank) = rank {
+            write!(
+                self,
+                "{}High score #{}\r\n",
+                color::Fg(color::Rgb(255, 255, 196)),
+                rank
+            )
+            .unwrap();
+        }
+
+        write!(
+            self,
+            "{}{}: Score: {} Lines: {} Level: {} Time: {}\r\n",
+            color::Fg(color::Rgb(196, 196, 196)),
+            game.get_mode(),
+            game.get_score(),
+            game.get_lines(),
+            game.get_level(),
+            time(game.get_frame())
+        )
+        .unwrap();
+        for line in stats_info(game) {
+            write!(self, "{}\r\n", line).unwrap();
+        }
+        write!(self, "\r\np. Play again\r\nm. Menu\r\nq. Quit\r\n").unwrap();
+
+        self.flush().unwrap();
+    }
+
+    fn show_high_scores(&mut self, mode: Mode, scores: &[&HighScore]) {
+        clear_screen(self);
+
+        write!(self, "High scores: {}\r\n\r\n", mode).unwrap();
+        write!(
+            self,
+            " #  Name                Score Lines Level      Time  Date        Seed\r\n"
+        )
+        .unwrap();
+        for (i, score) in scores.iter().enumerate() {
+            write!(
+                self,
+                "{:>2}  {:<16} {:>8} {:>5} {:>5} {:>9}  {:<10}  {}\r\n",
+                i + 1,
+                score.name,
+                score.score,
+                score.lines,
+                score.level,
+                time(score.frames),
+                score.day(),
+                score.seed
+            )
+            .unwrap();
+        }
+        if scores.is_empty() {
+            write!(self, "No scores yet\r\n").unwrap();
+        }
+
+        write!(self, "\r\nm. Next mode\r\nb. Back\r\n").unwrap();
+        self.flush().unwrap();
+    }
 }
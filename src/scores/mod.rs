@@ -0,0 +1,307 @@
+use super::storage;
+use super::tris::{Game, Mode};
+
+use std::fmt;
+use std::str::FromStr;
+
+pub const SCORES_FILE: &str = "scores.txt";
+// The number of scores kept for each mode.
+pub const TABLE_SIZE: usize = 10;
+pub const MAX_NAME: usize = 16;
+
+const SCORES_VERSION: u32 = 1;
+const SCORES_HEADER: &str = "textris-scores";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighScore {
+    pub mode: Mode,
+    pub score: isize,
+    pub lines: usize,
+    pub level: usize,
+    pub frames: u64,
+    // Seconds since the unix epoch
+    pub date: u64,
+    pub seed: u64,
+    pub name: String,
+}
+
+impl HighScore {
+    pub fn new(game: &Game, name: &str, date: u64) -> Self {
+        Self {
+            mode: game.get_mode(),
+            score: game.get_score(),
+            lines: game.get_lines(),
+            level: game.get_level(),
+            frames: game.get_frame(),
+            date,
+            seed: game.get_seed(),
+            name: name.to_string(),
+        }
+    }
+
+    // Races are won on time, and everything else on score.
+    fn beats(&self, other: &HighScore) -> bool {
+        if ranks_by_time(self.mode) {
+            self.frames < other.frames
+        } else {
+            self.score > other.score
+        }
+    }
+
+    // The date as YYYY-MM-DD.
+    pub fn day(&self) -> String {
+        let (y, m, d) = civil_from_days((self.date / SECONDS_PER_DAY) as i64);
+        format!("{:04}-{:02}-{:02}", y, m, d)
+    }
+}
+
+fn ranks_by_time(mode: Mode) -> bool {
+    mode == Mode::Sprint || mode == Mode::Dig
+}
+
+// Turns a number of days since 1970-01-01 into a year, month and day, using
+// the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+
+    (y, m, d)
+}
+
+// One score per line, with the name last so that it can have spaces in it.
+impl fmt::Display for HighScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {}",
+            self.mode.to_string().to_lowercase(),
+            self.score,
+            self.lines,
+            self.level,
+            self.frames,
+            self.date,
+            self.seed,
+            self.name
+        )
+    }
+}
+
+impl FromStr for HighScore {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid high score '{}'", s);
+
+        let mut fields = s.splitn(8, ' ');
+        let mut next = || fields.next().ok_or_else(invalid);
+
+        Ok(Self {
+            mode: next()?.parse().map_err(|_| invalid())?,
+            score: next()?.parse().map_err(|_| invalid())?,
+            lines: next()?.parse().map_err(|_| invalid())?,
+            level: next()?.parse().map_err(|_| invalid())?,
+            frames: next()?.parse().map_err(|_| invalid())?,
+            date: next()?.parse().map_err(|_| invalid())?,
+            seed: next()?.parse().map_err(|_| invalid())?,
+            name: next()?.to_string(),
+        })
+    }
+}
+
+// The best scores for each mode, kept in the data directory.
+pub struct HighScores {
+    scores: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn new() -> Self {
+        Self { scores: vec![] }
+    }
+
+    pub fn load() -> Result<Self, String> {
+        match storage::read(SCORES_FILE)? {
+            Some(s) => s.parse(),
+            None => Ok(Self::new()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::write(SCORES_FILE, &self.to_string())
+    }
+
+    // The mode's scores, best first.
+    pub fn table(&self, mode: Mode) -> Vec<&HighScore> {
+        let mut table: Vec<&HighScore> = self.scores.iter().filter(|s| s.mode == mode).collect();
+        table.sort_by(|a, b| {
+            if a.beats(b) {
+                std::cmp::Ordering::Less
+            } else if b.beats(a) {
+                std::cmp::Ordering::Greater
+            } else {
+                a.date.cmp(&b.date)
+            }
+        });
+        table
+    }
+
+    // Whether the game would make it into its mode's table. A race only
+    // counts if it was finished.
+    pub fn qualifies(&self, game: &Game) -> bool {
+        if ranks_by_time(game.get_mode()) && !game.is_finished() {
+            return false;
+        }
+
+        let score = HighScore::new(game, "", u64::MAX);
+        let table = self.table(score.mode);
+        table.len() < TABLE_SIZE || table.iter().any(|s| score.beats(s))
+    }
+
+    // Adds the score to its table, dropping whatever falls off the end.
+    // Returns where it came, counting from 1, if it made it in.
+    pub fn add(&mut self, score: HighScore) -> Option<usize> {
+        let mode = score.mode;
+        self.scores.push(score.clone());
+
+        let table: Vec<HighScore> = self
+            .table(mode)
+            .into_iter()
+            .take(TABLE_SIZE)
+            .cloned()
+            .collect();
+        let rank = table.iter().position(|s| *s == score).map(|i| i + 1);
+
+        self.scores.retain(|s| s.mode != mode);
+        self.scores.extend(table);
+
+        rank
+    }
+}
+
+impl fmt::Display for HighScores {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", SCORES_HEADER, SCORES_VERSION)?;
+        for score in self.scores.iter() {
+            writeln!(f, "{}", score)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HighScores {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+
+        let header = lines.next().unwrap_or("");
+        if header != format!("{} {}", SCORES_HEADER, SCORES_VERSION) {
+            return Err("not a textris high score file, or an unsupported version".to_string());
+        }
+
+        let scores = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| line.parse::<HighScore>())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { scores })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tris::Config;
+    use super::*;
+
+    fn score(mode: Mode, score: isize, frames: u64, date: u64) -> HighScore {
+        HighScore {
+            mode,
+            score,
+            lines: 10,
+            level: 2,
+            frames,
+            date,
+            seed: 42,
+            name: format!("player {}", score),
+        }
+    }
+
+    #[test]
+    fn high_scores_round_trip() {
+        let mut h = HighScores::new();
+        h.add(score(Mode::Marathon, 1000, 600, 1));
+        h.add(score(Mode::Sprint, 0, 3600, 2));
+
+        let parsed: HighScores = h.to_string().parse().expect("could not parse high scores");
+        assert_eq!(parsed.scores, h.scores);
+        assert_eq!(parsed.table(Mode::Marathon)[0].name, "player 1000");
+
+        assert!("".parse::<HighScores>().is_err());
+        assert!("textris-scores 2\n".parse::<HighScores>().is_err());
+        assert!("textris-scores 1\nmarathon 1 2\n"
+            .parse::<HighScores>()
+            .is_err());
+    }
+
+    #[test]
+    fn high_scores_add() {
+        let mut h = HighScores::new();
+
+        for i in 0..TABLE_SIZE as isize {
+            assert_eq!(h.add(score(Mode::Marathon, i * 100, 0, i as u64)), Some(1));
+        }
+        assert_eq!(h.table(Mode::Marathon).len(), TABLE_SIZE);
+
+        // Too low to make it, and then a new second place
+        assert_eq!(h.add(score(Mode::Marathon, -1, 0, 20)), None);
+        assert_eq!(h.add(score(Mode::Marathon, 850, 0, 21)), Some(2));
+        let table = h.table(Mode::Marathon);
+        assert_eq!(table.len(), TABLE_SIZE);
+        assert_eq!(table[0].score, 900);
+        assert_eq!(table[1].score, 850);
+        assert_eq!(table[TABLE_SIZE - 1].score, 100);
+
+        // Other modes have their own tables, and races are won on time
+        assert_eq!(h.add(score(Mode::Sprint, 0, 3000, 22)), Some(1));
+        assert_eq!(h.add(score(Mode::Sprint, 100, 4000, 23)), Some(2));
+        assert_eq!(h.add(score(Mode::Sprint, 0, 2000, 24)), Some(1));
+        assert_eq!(h.table(Mode::Marathon).len(), TABLE_SIZE);
+    }
+
+    #[test]
+    fn high_scores_qualifies() {
+        let mut c = Config {
+            mode: Mode::Sprint,
+            ..Default::default()
+        };
+        let g = Game::new(&c).expect("could not create new game");
+
+        let h = HighScores::new();
+        assert!(!h.qualifies(&g), "unfinished sprints don't count");
+
+        c.mode = Mode::Marathon;
+        let g = Game::new(&c).expect("could not create new game");
+        assert!(h.qualifies(&g));
+    }
+
+    #[test]
+    fn high_score_day() {
+        let cases: Vec<(u64, &str)> = vec![
+            (0, "1970-01-01"),
+            (951_782_400, "2000-02-29"),
+            (1_700_000_000, "2023-11-14"),
+        ];
+
+        for case in cases {
+            let (date, want) = case;
+            assert_eq!(score(Mode::Zen, 0, 0, date).day(), want);
+        }
+    }
+}
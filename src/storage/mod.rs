@@ -0,0 +1,144 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+const APP_DIR: &str = "textris";
+
+pub const SAVE_FILE: &str = "save.txt";
+
+// Where textris keeps its files, following the XDG base directory spec:
+// $XDG_DATA_HOME/textris, falling back to ~/.local/share/textris.
+pub fn data_dir() -> Result<PathBuf, String> {
+    data_dir_from(env::var("XDG_DATA_HOME").ok(), env::var("HOME").ok())
+}
+
+// Where the player's settings are read from: $XDG_CONFIG_HOME/textris,
+// falling back to ~/.config/textris.
+pub fn config_dir() -> Result<PathBuf, String> {
+    config_dir_from(env::var("XDG_CONFIG_HOME").ok(), env::var("HOME").ok())
+}
+
+fn data_dir_from(xdg_data_home: Option<String>, home: Option<String>) -> Result<PathBuf, String> {
+    base_dir("XDG_DATA_HOME", xdg_data_home, home, &[".local", "share"])
+}
+
+fn config_dir_from(
+    xdg_config_home: Option<String>,
+    home: Option<String>,
+) -> Result<PathBuf, String> {
+    base_dir("XDG_CONFIG_HOME", xdg_config_home, home, &[".config"])
+}
+
+fn base_dir(
+    var: &str,
+    xdg: Option<String>,
+    home: Option<String>,
+    fallback: &[&str],
+) -> Result<PathBuf, String> {
+    // Relative paths are to be ignored
+    let base = match xdg.map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => match home {
+            Some(home) if !home.is_empty() => fallback
+                .iter()
+                .fold(PathBuf::from(home), |path, dir| path.join(dir)),
+            _ => return Err(format!("neither {} nor HOME is set", var)),
+        },
+    };
+
+    Ok(base.join(APP_DIR))
+}
+
+fn read_path(path: PathBuf) -> Result<Option<String>, String> {
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("could not read {}: {}", path.display(), e)),
+    }
+}
+
+// Reads a file from the data directory, if it is there.
+pub fn read(name: &str) -> Result<Option<String>, String> {
+    read_path(data_dir()?.join(name))
+}
+
+// Reads a file from the config directory, if it is there.
+pub fn read_config(name: &str) -> Result<Option<String>, String> {
+    read_path(config_dir()?.join(name))
+}
+
+pub fn write(name: &str, contents: &str) -> Result<(), String> {
+    let dir = data_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+
+    let path = dir.join(name);
+    fs::write(&path, contents).map_err(|e| format!("could not write {}: {}", path.display(), e))
+}
+
+pub fn remove(name: &str) -> Result<(), String> {
+    let path = data_dir()?.join(name);
+
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            Err(format!("could not remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_data_dir() {
+        let cases: Vec<(Option<&str>, Option<&str>, Option<&str>)> = vec![
+            (Some("/data"), Some("/home/me"), Some("/data/textris")),
+            (
+                None,
+                Some("/home/me"),
+                Some("/home/me/.local/share/textris"),
+            ),
+            (
+                Some("data"),
+                Some("/home/me"),
+                Some("/home/me/.local/share/textris"),
+            ),
+            (
+                Some(""),
+                Some("/home/me"),
+                Some("/home/me/.local/share/textris"),
+            ),
+            (Some("/data"), None, Some("/data/textris")),
+            (None, None, None),
+            (None, Some(""), None),
+        ];
+
+        for case in cases {
+            let (xdg, home, want) = case;
+            let got = data_dir_from(xdg.map(String::from), home.map(String::from)).ok();
+            assert_eq!(got, want.map(PathBuf::from), "{:?} {:?}", xdg, home);
+        }
+    }
+
+    #[test]
+    fn storage_config_dir() {
+        let cases: Vec<(Option<&str>, Option<&str>, Option<&str>)> = vec![
+            (Some("/config"), Some("/home/me"), Some("/config/textris")),
+            (None, Some("/home/me"), Some("/home/me/.config/textris")),
+            (
+                Some("config"),
+                Some("/home/me"),
+                Some("/home/me/.config/textris"),
+            ),
+            (None, None, None),
+        ];
+
+        for case in cases {
+            let (xdg, home, want) = case;
+            let got = config_dir_from(xdg.map(String::from), home.map(String::from)).ok();
+            assert_eq!(got, want.map(PathBuf::from), "{:?} {:?}", xdg, home);
+        }
+    }
+}
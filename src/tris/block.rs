@@ -1,111 +1,96 @@
 use super::Colour;
+use super::PieceKind;
 
 type BlockValue = u16;
 const BLOCK_SPAN: isize = 4;
-const BLOCK_TYPE_COUNT: usize = 7;
 
 pub struct Block {
     value: BlockValue,
-    w: isize,
-    h: isize,
+    size: isize,
+    rotation: usize,
     colour: Colour,
+    kind: PieceKind,
 }
 
 impl Block {
     pub fn new() -> Self {
         Self {
             value: 0x0000,
-            w: 0,
-            h: 0,
+            size: 0,
+            rotation: 0,
             colour: Colour::Empty,
+            kind: PieceKind::I,
         }
     }
 
-    fn setup_block(&mut self, block_type: usize) -> (isize, isize) {
-        let block_type = block_type % BLOCK_TYPE_COUNT;
-
-        match block_type {
-            0 => self.setup(
-                0x0002 | 0x0010 | 0x0020 | 0x0040,
-                3,
-                2,
-                Colour::Value(block_type),
-            ), //T
-            1 => self.setup(
-                0x0001 | 0x0002 | 0x0004 | 0x0010,
-                3,
-                2,
-                Colour::Value(block_type),
-            ), //LL
-            2 => self.setup(
-                0x0001 | 0x0002 | 0x0004 | 0x0040,
-                3,
-                2,
-                Colour::Value(block_type),
-            ), //RL
-            3 => self.setup(
-                0x0001 | 0x0002 | 0x0010 | 0x0020,
-                2,
-                2,
-                Colour::Value(block_type),
-            ), //B
-            4 => self.setup(
-                0x0001 | 0x0002 | 0x0020 | 0x0040,
-                3,
-                2,
-                Colour::Value(block_type),
-            ), //LZ
-            5 => self.setup(
-                0x0002 | 0x0004 | 0x0010 | 0x0020,
-                3,
-                2,
-                Colour::Value(block_type),
-            ), //RZ
-            6 => self.setup(
-                0x0001 | 0x0002 | 0x0004 | 0x0008,
-                4,
-                1,
-                Colour::Value(block_type),
-            ), //I
-            _ => (0, 0),
+    pub fn with_kind(kind: PieceKind) -> Self {
+        let mut b = Self::new();
+        b.setup_block(kind);
+        b
+    }
+
+    // Blocks are laid out in their SRS spawn orientation, within the square
+    // box they rotate in.
+    pub fn setup_block(&mut self, kind: PieceKind) {
+        self.kind = kind;
+
+        match kind {
+            PieceKind::T => self.setup(0x0002 | 0x0010 | 0x0020 | 0x0040, 3, kind.colour()),
+            PieceKind::L => self.setup(0x0004 | 0x0010 | 0x0020 | 0x0040, 3, kind.colour()),
+            PieceKind::J => self.setup(0x0001 | 0x0010 | 0x0020 | 0x0040, 3, kind.colour()),
+            PieceKind::O => self.setup(0x0001 | 0x0002 | 0x0010 | 0x0020, 2, kind.colour()),
+            PieceKind::Z => self.setup(0x0001 | 0x0002 | 0x0020 | 0x0040, 3, kind.colour()),
+            PieceKind::S => self.setup(0x0002 | 0x0004 | 0x0010 | 0x0020, 3, kind.colour()),
+            PieceKind::I => self.setup(0x0010 | 0x0020 | 0x0040 | 0x0080, 4, kind.colour()),
         }
     }
 
-    fn setup(&mut self, value: BlockValue, w: isize, h: isize, colour: Colour) -> (isize, isize) {
+    fn setup(&mut self, value: BlockValue, size: isize, colour: Colour) {
         self.value = value;
-        self.w = w;
-        self.h = h;
+        self.size = size;
+        self.rotation = 0;
         self.colour = colour;
-        (-w / 2, 0)
     }
 
     #[cfg(test)]
-    pub fn test(&mut self) -> (isize, isize) {
-        self.setup_block(0)
+    pub fn test(&mut self) {
+        self.setup_block(PieceKind::T)
     }
 
-    pub fn random(&mut self) -> (isize, isize) {
-        self.setup_block(rand::random::<usize>())
+    pub fn kind(&self) -> PieceKind {
+        self.kind
     }
 
     pub fn colour(&self) -> Colour {
         self.colour
     }
 
-    // 0:0 -> w:0
-    // w:0 -> h:w
-    // w:h -> 0:w
-    // 0:h -> 0:0
+    // The SRS rotation state: 0 (spawn), 1 (R), 2 (180) or 3 (L).
+    pub fn rotation(&self) -> usize {
+        self.rotation
+    }
+
+    // The first row with anything in it.
+    pub fn top(&self) -> isize {
+        (0..self.size)
+            .find(|y| (0..self.size).any(|x| self.get(x, *y)))
+            .unwrap_or(0)
+    }
+
+    // Rotates about the centre of the box:
+    // 0:0 -> s:0
+    // s:0 -> s:s
+    // s:s -> 0:s
+    // 0:s -> 0:0
     pub fn rotate_clockwise(&mut self) {
         let mut v: BlockValue = 0;
-        let w = self.w;
-        let h = self.h;
+        let s = self.size;
 
-        for y in 0..h {
-            for x in 0..w {
+        for y in 0..s {
+            for x in 0..s {
                 let m: BlockValue = 1 << (y * BLOCK_SPAN + x);
                 if self.value & m == m {
-                    let nx = h - y - 1;
+                    let nx = s - y - 1;
                     let ny = x;
                     let nm: BlockValue = 1 << (ny * BLOCK_SPAN + nx);
                     v |= nm
@@ -114,25 +99,24 @@ impl Block {
         }
 
         self.value = v;
-        self.w = h;
-        self.h = w;
+        self.rotation = (self.rotation + 1) % 4;
     }
 
-    // 0:0 -> 0:w
-    // w:0 -> 0:0
-    // w:h -> h:0
-    // 0:h -> h:w
+    // Rotates about the centre of the box:
+    // 0:0 -> 0:s
+    // s:0 -> 0:0
+    // s:s -> s:0
+    // 0:s -> s:s
     pub fn rotate_anticlockwise(&mut self) {
         let mut v: BlockValue = 0;
-        let w = self.w;
-        let h = self.h;
+        let s = self.size;
 
-        for y in 0..h {
-            for x in 0..w {
+        for y in 0..s {
+            for x in 0..s {
                 let m: BlockValue = 1 << (y * BLOCK_SPAN + x);
                 if self.value & m == m {
                     let nx = y;
-                    let ny = w - x - 1;
+                    let ny = s - x - 1;
                     let nm: BlockValue = 1 << (ny * BLOCK_SPAN + nx);
                     v |= nm
                 }
@@ -140,16 +124,15 @@ impl Block {
         }
 
         self.value = v;
-        self.w = h;
-        self.h = w;
+        self.rotation = (self.rotation + 3) % 4;
     }
 
     pub fn dims(&self) -> (isize, isize) {
-        (self.w, self.h)
+        (self.size, self.size)
     }
 
     pub fn get(&self, x: isize, y: isize) -> bool {
-        if x < 0 || x >= self.w || y < 0 || y >= self.h {
+        if x < 0 || x >= self.size || y < 0 || y >= self.size {
             false
         } else {
             let m = 1 << (y * BLOCK_SPAN + x);
@@ -167,8 +150,8 @@ mod tests {
         let b = Block::new();
 
         assert_eq!(b.value, 0);
-        assert_eq!(b.w, 0);
-        assert_eq!(b.h, 0);
+        assert_eq!(b.size, 0);
+        assert_eq!(b.rotation, 0);
         assert_eq!(b.colour, Colour::Empty);
     }
 
@@ -176,16 +159,16 @@ mod tests {
     fn block_setup() {
         let mut b = Block::new();
 
-        let cases: Vec<(BlockValue, isize, isize, Colour)> = vec![(0x0072, 3, 2, Colour::Value(0))];
+        let cases: Vec<(BlockValue, isize, Colour)> = vec![(0x0072, 3, Colour::Value(0))];
 
         for case in cases {
-            let (value, width, height, colour) = case;
+            let (value, size, colour) = case;
 
-            b.setup(value, width, height, colour);
+            b.setup(value, size, colour);
 
             assert_eq!(b.value, value);
-            assert_eq!(b.w, width);
-            assert_eq!(b.h, height);
+            assert_eq!(b.size, size);
+            assert_eq!(b.rotation, 0);
             assert_eq!(b.colour, colour);
         }
     }
@@ -194,42 +177,56 @@ mod tests {
     fn block_get() {
         let mut b = Block::new();
 
-        b.setup(0xffff, 2, 2, Colour::Value(0));
-        assert_eq!(b.get(-1, -1), false);
-        assert_eq!(b.get(0, 0), true);
-        assert_eq!(b.get(1, 0), true);
-        assert_eq!(b.get(0, 1), true);
-        assert_eq!(b.get(1, 1), true);
-        assert_eq!(b.get(2, 2), false);
+        b.setup(0xffff, 2, Colour::Value(0));
+        assert!(!b.get(-1, -1));
+        assert!(b.get(0, 0));
+        assert!(b.get(1, 0));
+        assert!(b.get(0, 1));
+        assert!(b.get(1, 1));
+        assert!(!b.get(2, 2));
+    }
+
+    #[test]
+    fn block_top() {
+        let mut b = Block::new();
+
+        let cases: Vec<(PieceKind, isize)> =
+            vec![(PieceKind::T, 0), (PieceKind::O, 0), (PieceKind::I, 1)];
+
+        for case in cases {
+            let (kind, want) = case;
+
+            b.setup_block(kind);
+            assert_eq!(b.top(), want);
+        }
     }
 
     #[test]
     fn block_rotate_clockwise() {
         let mut b = Block::new();
 
-        let cases: Vec<(BlockValue, isize, isize, BlockValue, isize, isize)> =
-            vec![(0x0072, 3, 2, 0x0131, 2, 3)];
+        let cases: Vec<(BlockValue, isize, BlockValue)> = vec![
+            (0x0072, 3, 0x0262),
+            (0x00f0, 4, 0x4444),
+            (0x0033, 2, 0x0033),
+        ];
 
         for case in cases {
-            let (value, w, h, want_value, want_w, want_h) = case;
+            let (value, size, want_value) = case;
 
-            b.setup(value, w, h, Colour::Value(0));
+            b.setup(value, size, Colour::Value(0));
             b.rotate_clockwise();
 
             assert!(
                 b.value == want_value,
-                format!(
-                    "received value 0x{0:04x?} instead of 0x{1:04x?}",
-                    b.value, want_value
-                )
-            );
-            assert!(
-                b.w == want_w,
-                format!("received w {0} instead of {1}", b.w, want_w)
+                "received value 0x{0:04x?} instead of 0x{1:04x?}",
+                b.value,
+                want_value
             );
             assert!(
-                b.h == want_h,
-                format!("received h {0} instead of {1}", b.h, want_h)
+                b.rotation == 1,
+                "received rotation {0} instead of 1",
+                b.rotation
             );
         }
     }
@@ -238,30 +235,50 @@ mod tests {
     fn block_rotate_anticlockwise() {
         let mut b = Block::new();
 
-        let cases: Vec<(BlockValue, isize, isize, BlockValue, isize, isize)> =
-            vec![(0x0072, 3, 2, 0x0232, 2, 3)];
+        let cases: Vec<(BlockValue, isize, BlockValue)> = vec![
+            (0x0072, 3, 0x0232),
+            (0x00f0, 4, 0x2222),
+            (0x0033, 2, 0x0033),
+        ];
 
         for case in cases {
-            let (value, w, h, want_value, want_w, want_h) = case;
+            let (value, size, want_value) = case;
 
-            b.setup(value, w, h, Colour::Value(0));
+            b.setup(value, size, Colour::Value(0));
             b.rotate_anticlockwise();
 
             assert!(
                 b.value == want_value,
-                format!(
-                    "received value 0x{0:04x?} instead of 0x{1:04x?}",
-                    b.value, want_value
-                )
-            );
-            assert!(
-                b.w == want_w,
-                format!("received w {0} instead of {1}", b.w, want_w)
+                "received value 0x{0:04x?} instead of 0x{1:04x?}",
+                b.value,
+                want_value
             );
             assert!(
-                b.h == want_h,
-                format!("received h {0} instead of {1}", b.h, want_h)
+                b.rotation == 3,
+                "received rotation {0} instead of 3",
+                b.rotation
             );
         }
     }
+
+    #[test]
+    fn block_rotate_full_turn() {
+        let mut b = Block::new();
+
+        for kind in PieceKind::ALL.iter() {
+            b.setup_block(*kind);
+            let value = b.value;
+
+            for _ in 0..4 {
+                b.rotate_clockwise();
+            }
+            assert_eq!(b.value, value);
+            assert_eq!(b.rotation, 0);
+
+            b.rotate_anticlockwise();
+            b.rotate_clockwise();
+            assert_eq!(b.value, value);
+            assert_eq!(b.rotation, 0);
+        }
+    }
 }
@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+// Where Game gets the time from, so that callers can stop, slow down or
+// script it.
+pub trait Clock {
+    // The time since some fixed starting point.
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// A clock that only moves when told to.
+#[cfg(test)]
+pub struct ManualClock {
+    now: std::cell::Cell<Duration>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: std::cell::Cell::new(Duration::from_secs(0)),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_manual() {
+        let c = ManualClock::new();
+
+        assert_eq!(c.now(), Duration::from_secs(0));
+        c.advance(Duration::from_millis(5));
+        c.advance(Duration::from_millis(10));
+        assert_eq!(c.now(), Duration::from_millis(15));
+    }
+
+    #[test]
+    fn clock_system() {
+        let c = SystemClock::new();
+
+        let a = c.now();
+        let b = c.now();
+        assert!(b >= a);
+    }
+}
@@ -4,6 +4,8 @@ use std::fmt;
 pub enum Colour {
     Empty,
     Value(usize),
+    Ghost(usize),
+    Garbage,
 }
 
 impl fmt::Debug for Colour {
@@ -11,6 +13,8 @@ impl fmt::Debug for Colour {
         match self {
             Colour::Empty => write!(f, "empty"),
             Colour::Value(v) => write!(f, "#{0}", v),
+            Colour::Ghost(v) => write!(f, "ghost#{0}", v),
+            Colour::Garbage => write!(f, "garbage"),
         }
     }
 }
@@ -20,6 +24,8 @@ impl fmt::Display for Colour {
         match self {
             Colour::Empty => write!(f, "empty"),
             Colour::Value(v) => write!(f, "#{0}", v),
+            Colour::Ghost(v) => write!(f, "ghost#{0}", v),
+            Colour::Garbage => write!(f, "garbage"),
         }
     }
 }
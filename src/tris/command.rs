@@ -0,0 +1,12 @@
+// Something the player does to the active block during a frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Command {
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateAnticlockwise,
+    Rotate180,
+    Hold,
+}
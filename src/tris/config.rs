@@ -0,0 +1,41 @@
+use super::Gravity;
+use super::LockReset;
+use super::Mode;
+use super::RandomizerKind;
+
+#[derive(Clone)]
+pub struct Config {
+    pub width: isize,
+    pub height: isize,
+    pub seed: u64,
+    pub randomizer: RandomizerKind,
+    pub preview: usize,
+    pub gravity: Gravity,
+    pub lines_per_level: usize,
+    pub lock_delay: u32,
+    pub lock_reset: LockReset,
+    pub move_limit: usize,
+    pub mode: Mode,
+    pub dig_lines: usize,
+    pub messiness: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            width: 11,
+            height: 20,
+            seed: 0,
+            randomizer: RandomizerKind::Bag,
+            preview: 3,
+            gravity: Gravity::default(),
+            lines_per_level: 10,
+            lock_delay: 30,
+            lock_reset: LockReset::Move,
+            move_limit: 15,
+            mode: Mode::Marathon,
+            dig_lines: 10,
+            messiness: 30,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use super::Kick;
+use super::PieceKind;
+use super::{ClearKind, TSpin};
+
+use std::fmt;
+
+// Something that happened in a game, for anything that wants to react to it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    Spawned(PieceKind),
+    Moved {
+        dx: isize,
+        dy: isize,
+    },
+    // Turned clockwise by the given number of quarter turns: 1, 2 or 3.
+    Rotated {
+        turns: usize,
+        kick: Kick,
+    },
+    Held(PieceKind),
+    Locked(PieceKind),
+    LinesCleared {
+        clear: ClearKind,
+        tspin: Option<TSpin>,
+    },
+    LevelUp(usize),
+    Garbage(usize),
+    Paused,
+    Resumed,
+    TopOut,
+    Finished,
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameEvent::Spawned(kind) => write!(f, "spawned {}", kind),
+            GameEvent::Moved { dx, dy } => write!(f, "moved {},{}", dx, dy),
+            GameEvent::Rotated { turns, kick } => write!(
+                f,
+                "rotated {} (kick {}: {},{})",
+                match turns {
+                    1 => "clockwise",
+                    2 => "180",
+                    _ => "anticlockwise",
+                },
+                kick.test,
+                kick.dx,
+                kick.dy
+            ),
+            GameEvent::Held(kind) => write!(f, "held {}", kind),
+            GameEvent::Locked(kind) => write!(f, "locked {}", kind),
+            GameEvent::LinesCleared { clear, tspin } => match tspin {
+                Some(tspin) => write!(f, "cleared {} {}", tspin, clear),
+                None => write!(f, "cleared {}", clear),
+            },
+            GameEvent::LevelUp(level) => write!(f, "level {}", level),
+            GameEvent::Garbage(rows) => write!(f, "{} garbage rows", rows),
+            GameEvent::Paused => write!(f, "paused"),
+            GameEvent::Resumed => write!(f, "resumed"),
+            GameEvent::TopOut => write!(f, "topped out"),
+            GameEvent::Finished => write!(f, "finished"),
+        }
+    }
+}
@@ -1,5 +1,32 @@
+use super::gravity::{FRAME_NANOS, G};
+use super::save::{self, SaveReader, SaveWriter};
+use super::srs;
 use super::Block;
+use super::ClearKind;
+use super::Clock;
 use super::Colour;
+use super::Command;
+use super::Config;
+use super::GameEvent;
+use super::Gravity;
+use super::Kick;
+use super::LockReset;
+use super::Mode;
+use super::PieceKind;
+use super::Rng;
+use super::Stats;
+use super::{Randomizer, RandomizerKind};
+use super::{ScoreEvent, Scoring, TSpin};
+
+use std::collections::VecDeque;
+
+pub const MIN_PREVIEW: usize = 1;
+pub const MAX_PREVIEW: usize = 6;
+pub const MAX_MESSINESS: u32 = 100;
+// The most garbage rows dig mode keeps on the board at once.
+const DIG_ROWS: usize = 9;
+// Mixed into the seed so that garbage doesn't use up the pieces' numbers.
+const GARBAGE_SALT: u64 = 0x6761_7262_6167_6521;
 
 pub struct Game {
     x: isize,
@@ -8,16 +35,58 @@ pub struct Game {
     w: isize,
     h: isize,
     board: Vec<Colour>,
-    score: isize,
-    last_fall: std::time::Instant,
-    fall_rate_nanos: u128,
+    scoring: Scoring,
+    stats: Stats,
+    seed: u64,
+    rng: Rng,
+    randomizer_kind: RandomizerKind,
+    randomizer: Box<dyn Randomizer>,
+    preview: VecDeque<PieceKind>,
+    preview_len: usize,
+    hold: Option<PieceKind>,
+    can_hold: bool,
+    gravity: Gravity,
+    lines_per_level: usize,
+    level: usize,
+    lines: usize,
+    last_frame: Option<std::time::Duration>,
+    fall: u32,
+    lock_delay: u32,
+    lock_reset: LockReset,
+    move_limit: usize,
+    lock_frames: u32,
+    lock_resets: usize,
+    lowest_y: isize,
+    last_kick: Option<Kick>,
+    events: VecDeque<GameEvent>,
+    mode: Mode,
+    garbage_rng: Rng,
+    garbage_hole: Option<isize>,
+    messiness: u32,
+    dig_lines: usize,
+    garbage_cleared: usize,
+    frame: u64,
+    paused: bool,
+    finished: bool,
     game_over: bool,
 }
 
 impl Game {
-    pub fn new(w: isize, h: isize) -> Result<Self, String> {
+    pub fn new(config: &Config) -> Result<Self, String> {
+        let w = config.width;
+        let h = config.height;
+
         if w < 4 || h < 4 {
             Err("too small".to_string())
+        } else if config.preview < MIN_PREVIEW || config.preview > MAX_PREVIEW {
+            Err(format!(
+                "preview must be between {} and {}",
+                MIN_PREVIEW, MAX_PREVIEW
+            ))
+        } else if config.lines_per_level == 0 {
+            Err("lines per level must be at least 1".to_string())
+        } else if config.messiness > MAX_MESSINESS {
+            Err(format!("messiness must be at most {}", MAX_MESSINESS))
         } else {
             let mut board: Vec<Colour> = vec![];
 
@@ -27,12 +96,42 @@ impl Game {
                 x: 0,
                 y: 0,
                 block: super::Block::new(),
-                w: w,
-                h: h,
-                board: board,
-                score: 0,
-                last_fall: std::time::Instant::now(),
-                fall_rate_nanos: std::time::Duration::from_millis(1000).as_nanos(),
+                w,
+                h,
+                board,
+                scoring: Scoring::new(),
+                stats: Stats::new(),
+                seed: config.seed,
+                rng: Rng::new(config.seed),
+                randomizer_kind: config.randomizer,
+                randomizer: config.randomizer.create(),
+                preview: VecDeque::new(),
+                preview_len: config.preview,
+                hold: None,
+                can_hold: true,
+                gravity: config.gravity.clone(),
+                lines_per_level: config.lines_per_level,
+                level: 1,
+                lines: 0,
+                last_frame: None,
+                fall: 0,
+                lock_delay: config.lock_delay,
+                lock_reset: config.lock_reset,
+                move_limit: config.move_limit,
+                lock_frames: 0,
+                lock_resets: 0,
+                lowest_y: 0,
+                last_kick: None,
+                events: VecDeque::new(),
+                mode: config.mode,
+                garbage_rng: Rng::new(config.seed ^ GARBAGE_SALT),
+                garbage_hole: None,
+                messiness: config.messiness,
+                dig_lines: config.dig_lines,
+                garbage_cleared: 0,
+                frame: 0,
+                paused: false,
+                finished: false,
                 game_over: false,
             };
 
@@ -46,40 +145,497 @@ impl Game {
         for i in 0..(self.w * self.h) as usize {
             self.board[i] = Colour::Empty;
         }
+        self.rng = Rng::new(self.seed);
+        self.randomizer = self.randomizer_kind.create();
+        self.preview.clear();
+        while self.preview.len() < self.preview_len {
+            let kind = self.randomizer.next(&mut self.rng);
+            self.preview.push_back(kind);
+        }
+        self.hold = None;
+        self.events.clear();
+        self.stats = Stats::new();
+        self.garbage_rng = Rng::new(self.seed ^ GARBAGE_SALT);
+        self.garbage_hole = None;
+        self.garbage_cleared = 0;
+        self.dig();
         self.random();
-        self.score = 0;
+        self.scoring = Scoring::new();
+        self.level = 1;
+        self.lines = 0;
+        self.last_frame = None;
+        self.frame = 0;
+        self.paused = false;
+        self.finished = false;
         self.game_over = false;
     }
 
+    // Writes out everything needed to carry on with the game later.
+    pub fn save(&self) -> String {
+        let mut w = SaveWriter::new();
+
+        w.field("width", self.w);
+        w.field("height", self.h);
+        w.field("seed", self.seed);
+        w.field("randomizer", self.randomizer_kind);
+        w.field("preview_len", self.preview_len);
+        w.list("gravity", self.gravity.table());
+        w.field("lines_per_level", self.lines_per_level);
+        w.field("lock_delay", self.lock_delay);
+        w.field("lock_reset", self.lock_reset);
+        w.field("move_limit", self.move_limit);
+        w.field("mode", self.mode.to_string().to_lowercase());
+        w.field("dig_lines", self.dig_lines);
+        w.field("messiness", self.messiness);
+
+        let rows: Vec<String> = self
+            .board
+            .chunks(self.w as usize)
+            .map(|row| row.iter().map(|c| save::cell(*c)).collect())
+            .collect();
+        w.list("board", &rows);
+        w.field("piece", self.block.kind());
+        w.field("rotation", self.block.rotation());
+        w.field("x", self.x);
+        w.field("y", self.y);
+        w.field("rng", self.rng.state());
+        w.list("randomizer_state", &self.randomizer.state());
+        w.list("preview", &self.get_preview());
+        w.optional("hold", self.hold);
+        w.field("can_hold", self.can_hold);
+        self.scoring.save(&mut w);
+        self.stats.save(&mut w);
+        w.field("level", self.level);
+        w.field("lines", self.lines);
+        w.field("fall", self.fall);
+        w.field("lock_frames", self.lock_frames);
+        w.field("lock_resets", self.lock_resets);
+        w.field("lowest_y", self.lowest_y);
+        match self.last_kick {
+            Some(kick) => w.list("last_kick", &[kick.test as isize, kick.dx, kick.dy]),
+            None => w.list::<isize>("last_kick", &[]),
+        }
+        w.field("garbage_rng", self.garbage_rng.state());
+        w.optional("garbage_hole", self.garbage_hole);
+        w.field("garbage_cleared", self.garbage_cleared);
+        w.field("frame", self.frame);
+        w.field("finished", self.finished);
+        w.field("game_over", self.game_over);
+
+        w.finish()
+    }
+
+    // Picks a saved game back up. The clock starts again from the next
+    // tick, wherever it was when the game was saved.
+    pub fn load(s: &str) -> Result<Self, String> {
+        let r = SaveReader::new(s)?;
+
+        let config = Config {
+            width: r.field("width")?,
+            height: r.field("height")?,
+            seed: r.field("seed")?,
+            randomizer: r.field("randomizer")?,
+            preview: r.field("preview_len")?,
+            gravity: Gravity::from_table(r.list("gravity")?)?,
+            lines_per_level: r.field("lines_per_level")?,
+            lock_delay: r.field("lock_delay")?,
+            lock_reset: r.field("lock_reset")?,
+            move_limit: r.field("move_limit")?,
+            mode: r.field("mode")?,
+            dig_lines: r.field("dig_lines")?,
+            messiness: r.field("messiness")?,
+        };
+        let mut g = Self::new(&config)?;
+
+        let rows: Vec<String> = r.list("board")?;
+        if rows.len() != g.h as usize || rows.iter().any(|row| row.chars().count() != g.w as usize)
+        {
+            return Err("board in save is the wrong size".to_string());
+        }
+        g.board = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(save::colour)
+            .collect::<Result<_, _>>()?;
+
+        g.block.setup_block(r.field("piece")?);
+        for _ in 0..r.field::<usize>("rotation")? % 4 {
+            g.block.rotate_clockwise();
+        }
+        g.x = r.field("x")?;
+        g.y = r.field("y")?;
+        g.rng = Rng::from_state(r.field("rng")?)?;
+        g.randomizer.restore(&r.list("randomizer_state")?)?;
+        g.preview = r.list("preview")?.into_iter().collect();
+        if g.preview.len() != g.preview_len {
+            return Err("preview in save is the wrong length".to_string());
+        }
+        g.hold = r.optional("hold")?;
+        g.can_hold = r.field("can_hold")?;
+        g.scoring = Scoring::load(&r)?;
+        g.stats = Stats::load(&r)?;
+        g.level = r.field("level")?;
+        g.lines = r.field("lines")?;
+        g.fall = r.field("fall")?;
+        g.lock_frames = r.field("lock_frames")?;
+        g.lock_resets = r.field("lock_resets")?;
+        g.lowest_y = r.field("lowest_y")?;
+        g.last_kick = match r.list::<isize>("last_kick")?.as_slice() {
+            [] => None,
+            [test, dx, dy] if *test >= 0 => Some(Kick {
+                test: *test as usize,
+                dx: *dx,
+                dy: *dy,
+            }),
+            _ => return Err("invalid last_kick in save".to_string()),
+        };
+        g.garbage_rng = Rng::from_state(r.field("garbage_rng")?)?;
+        g.garbage_hole = r.optional("garbage_hole")?;
+        g.garbage_cleared = r.field("garbage_cleared")?;
+        g.frame = r.field("frame")?;
+        g.finished = r.field("finished")?;
+        g.game_over = r.field("game_over")?;
+        g.events.clear();
+
+        Ok(g)
+    }
+
     pub fn get_score(&self) -> isize {
-        self.score
+        self.scoring.score()
+    }
+
+    pub fn get_stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub fn get_last_score(&self) -> Option<ScoreEvent> {
+        self.scoring.last()
+    }
+
+    pub fn get_level(&self) -> usize {
+        self.level
+    }
+
+    pub fn get_lines(&self) -> usize {
+        self.lines
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
+    // The number of frames played so far.
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    // Whether the game ended by reaching the mode's goal.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // The number of garbage lines still to clear in dig mode.
+    pub fn get_garbage_left(&self) -> usize {
+        self.dig_lines.saturating_sub(self.garbage_cleared)
+    }
+
+    // Takes the oldest event that has not been looked at yet.
+    pub fn poll_event(&mut self) -> Option<GameEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn get_piece(&self) -> PieceKind {
+        self.block.kind()
+    }
+
+    pub fn get_preview(&self) -> Vec<PieceKind> {
+        self.preview.iter().cloned().collect()
+    }
+
+    pub fn get_hold(&self) -> Option<PieceKind> {
+        self.hold
     }
 
     pub fn random(&mut self) {
-        let (dx, dy) = self.block.random();
-        self.x = self.w / 2 + dx;
-        self.y = dy;
-        self.last_fall = std::time::Instant::now();
+        let next = self.randomizer.next(&mut self.rng);
+        self.preview.push_back(next);
+        let kind = self.preview.pop_front().unwrap();
+        self.stats.dealt(kind);
+        self.can_hold = true;
+        self.spawn(kind);
+    }
+
+    // Swaps the active block with the held one, at most once per block.
+    pub fn hold(&mut self) -> bool {
+        if !self.can_hold {
+            return false;
+        }
+
+        let kind = self.block.kind();
+        self.events.push_back(GameEvent::Held(kind));
+        match self.hold.replace(kind) {
+            Some(held) => self.spawn(held),
+            None => self.random(),
+        }
+        self.can_hold = false;
+
+        true
+    }
+
+    fn spawn(&mut self, kind: PieceKind) {
+        self.block.setup_block(kind);
+        let (bw, _) = self.block.dims();
+        self.x = (self.w - bw) / 2;
+        self.y = -self.block.top();
+        self.fall = 0;
+        self.lock_frames = 0;
+        self.lock_resets = 0;
+        self.lowest_y = self.y;
+        self.last_kick = None;
+        self.events.push_back(GameEvent::Spawned(kind));
 
         if self.collision(self.x, self.y) {
-            self.game_over = true;
+            if self.mode.can_top_out() {
+                self.game_over = true;
+                self.events.push_back(GameEvent::TopOut);
+            } else {
+                for cell in self.board.iter_mut() {
+                    *cell = Colour::Empty;
+                }
+            }
+        }
+    }
+
+    // The number of whole frames that have passed on the clock since this
+    // was last asked, for the caller to step through. Asking for the first
+    // time in a game only starts the clock.
+    pub fn frames_due(&mut self, clock: &dyn Clock) -> u64 {
+        if self.paused {
+            return 0;
         }
+
+        let now = clock.now();
+        let last_frame = *self.last_frame.get_or_insert(now);
+
+        let frames = (now - last_frame).as_nanos() as u64 / FRAME_NANOS;
+        self.last_frame = Some(last_frame + std::time::Duration::from_nanos(frames * FRAME_NANOS));
+
+        frames
     }
 
-    pub fn tick(&mut self) -> bool {
-        let now = std::time::Instant::now();
-        if now.duration_since(self.last_fall).as_nanos() < self.fall_rate_nanos {
+    // Holds everything where it is, gravity, lock delay and the mode's
+    // timer included, until resumed.
+    pub fn pause(&mut self) {
+        if self.paused || self.game_over {
+            return;
+        }
+
+        self.paused = true;
+        self.events.push_back(GameEvent::Paused);
+    }
+
+    // Carries on from where the game was paused, with the clock starting
+    // again on the next frames_due.
+    pub fn resume(&mut self) {
+        if !self.paused {
+            return;
+        }
+
+        self.paused = false;
+        self.last_frame = None;
+        self.events.push_back(GameEvent::Resumed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Advances the game by exactly one frame: the commands are applied in
+    // order, and then gravity. Returns whether anything changed.
+    pub fn step(&mut self, commands: &[Command]) -> bool {
+        if self.game_over || self.paused {
             return false;
         }
 
-        if !self.down() {
-            self.merge();
+        self.stats.input(commands.len());
+
+        let mut changed = false;
+        for command in commands {
+            if self.game_over {
+                break;
+            }
+            if self.apply(*command) {
+                changed = true;
+            }
+        }
+
+        if !self.game_over && self.gravity_frame() {
+            changed = true;
+        }
+
+        if !self.game_over {
+            self.frame += 1;
+            if self
+                .mode
+                .finished(self.lines, self.level, self.frame, self.get_garbage_left())
+            {
+                self.finished = true;
+                self.game_over = true;
+                self.events.push_back(GameEvent::Finished);
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    fn apply(&mut self, command: Command) -> bool {
+        match command {
+            Command::Left => self.slide(-1),
+            Command::Right => self.slide(1),
+            // Soft dropping onto the stack leaves locking to the lock delay
+            Command::SoftDrop => self.down(),
+            Command::HardDrop => {
+                self.drop();
+                self.lock();
+                true
+            }
+            Command::RotateClockwise => self.rotate_clockwise().is_some(),
+            Command::RotateAnticlockwise => self.rotate_anticlockwise().is_some(),
+            Command::Rotate180 => self.rotate_180().is_some(),
+            Command::Hold => self.hold(),
+        }
+    }
+
+    // Merges the active block into the board and brings in the next one.
+    fn lock(&mut self) {
+        self.merge();
+        self.dig();
+        if !self.game_over {
             self.random();
         }
+    }
+
+    // Tops the garbage on the board back up in dig mode, without adding
+    // more than there is left to clear.
+    fn dig(&mut self) {
+        if !self.mode.has_garbage() {
+            return;
+        }
+
+        let rows = (0..self.h)
+            .filter(|y| (0..self.w).any(|x| self.get(x, *y) == Colour::Garbage))
+            .count();
+        let want = DIG_ROWS
+            .min(self.h as usize / 2)
+            .min(self.get_garbage_left());
+        if want > rows {
+            self.add_garbage(want - rows);
+        }
+    }
+
+    // Pushes rows of garbage in from the bottom, each with one hole. The
+    // messiness is the percentage chance of a row's hole not lining up
+    // with the one below it. Anything pushed off the top tops out.
+    pub fn add_garbage(&mut self, rows: usize) {
+        let rows = (rows as isize).min(self.h);
+        if rows == 0 {
+            return;
+        }
+
+        if (0..rows).any(|y| (0..self.w).any(|x| self.filled(x, y))) {
+            self.game_over = true;
+        }
+
+        self.board.drain(0..(rows * self.w) as usize);
+        for _ in 0..rows {
+            let hole = self.next_garbage_hole();
+
+            for x in 0..self.w {
+                self.board.push(if x == hole {
+                    Colour::Empty
+                } else {
+                    Colour::Garbage
+                });
+            }
+        }
+
+        while self.collision(self.x, self.y) {
+            self.y -= 1;
+        }
+
+        self.events.push_back(GameEvent::Garbage(rows as usize));
+        if self.game_over {
+            self.events.push_back(GameEvent::TopOut);
+        }
+    }
+
+    fn next_garbage_hole(&mut self) -> isize {
+        let moved = self.garbage_rng.below(MAX_MESSINESS as usize) < self.messiness as usize;
+        let hole = match self.garbage_hole {
+            Some(hole) if !moved => hole,
+            _ => self.garbage_rng.below(self.w as usize) as isize,
+        };
+
+        self.garbage_hole = Some(hole);
+        hole
+    }
+
+    // Moves the active block down by one frame's worth of gravity, or runs
+    // down its lock delay if it is resting on something.
+    fn gravity_frame(&mut self) -> bool {
+        if self.resting() {
+            self.fall = 0;
+            self.lock_frames += 1;
+            if self.lock_frames >= self.lock_delay {
+                self.lock();
+                return true;
+            }
+            return false;
+        }
+
+        self.fall += self.gravity.at(self.level);
 
+        let y = self.y;
+        while self.fall >= G {
+            self.fall -= G;
+
+            if !self.fall_one() {
+                self.fall = 0;
+                break;
+            }
+        }
+
+        if self.y == y {
+            return false;
+        }
+
+        self.events.push_back(GameEvent::Moved {
+            dx: 0,
+            dy: self.y - y,
+        });
         true
     }
 
+    fn resting(&self) -> bool {
+        self.collision(self.x, self.y + 1) || self.out_of_bounds(self.x, self.y + 1)
+    }
+
+    // Called after a successful slide or rotation.
+    fn moved(&mut self) {
+        if self.lock_reset == LockReset::Move
+            && self.lock_frames > 0
+            && self.lock_resets < self.move_limit
+        {
+            self.lock_frames = 0;
+            self.lock_resets += 1;
+        }
+    }
+
     pub fn dims(&self) -> (isize, isize) {
         (self.w, self.h)
     }
@@ -97,20 +653,24 @@ impl Game {
         if x < 0 || x >= self.w || y < 0 || y >= self.h {
             Colour::Empty
         } else {
+            let index = (y * self.w + x) as usize;
+            let (gx, gy) = self.ghost();
+
             if self.block.get(x - self.x, y - self.y) {
                 self.block.colour()
+            } else if self.board[index] == Colour::Empty && self.block.get(x - gx, y - gy) {
+                match self.block.colour() {
+                    Colour::Value(i) => Colour::Ghost(i),
+                    colour => colour,
+                }
             } else {
-                let index = (y * self.w + x) as usize;
                 self.board[index]
             }
         }
     }
 
     pub fn filled(&self, x: isize, y: isize) -> bool {
-        match self.get(x, y) {
-            Colour::Empty => false,
-            _ => true,
-        }
+        !matches!(self.get(x, y), Colour::Empty)
     }
 
     fn set(&mut self, x: isize, y: isize, colour: Colour) {
@@ -145,10 +705,8 @@ impl Game {
 
         for by in 0..bh {
             for bx in 0..bw {
-                if self.block.get(bx, by) {
-                    if self.filled(x + bx, y + by) {
-                        return true;
-                    }
+                if self.block.get(bx, by) && self.filled(x + bx, y + by) {
+                    return true;
                 }
             }
         }
@@ -156,24 +714,76 @@ impl Game {
         false
     }
 
-    pub fn rotate_clockwise(&mut self) -> bool {
+    // Tries each kick in turn, moving the block by the first one that fits.
+    fn kick(&mut self, kicks: &[(isize, isize)]) -> Option<Kick> {
+        for (test, (dx, dy)) in kicks.iter().enumerate() {
+            let x = self.x + dx;
+            let y = self.y + dy;
+
+            if !self.collision(x, y) && !self.out_of_bounds(x, y) {
+                self.x = x;
+                self.y = y;
+                return Some(Kick {
+                    test,
+                    dx: *dx,
+                    dy: *dy,
+                });
+            }
+        }
+
+        None
+    }
+
+    pub fn rotate_clockwise(&mut self) -> Option<Kick> {
+        let kicks = srs::kicks(self.block.kind(), self.block.rotation(), true);
+
         self.block.rotate_clockwise();
-        if self.collision(self.x, self.y) || self.out_of_bounds(self.x, self.y) {
+        let kick = self.kick(kicks);
+        if kick.is_none() {
             self.block.rotate_anticlockwise();
-            return false;
+        } else {
+            self.moved();
+            self.rotated(1, kick);
         }
 
-        true
+        kick
     }
 
-    pub fn rotate_anticlockwise(&mut self) -> bool {
+    pub fn rotate_anticlockwise(&mut self) -> Option<Kick> {
+        let kicks = srs::kicks(self.block.kind(), self.block.rotation(), false);
+
         self.block.rotate_anticlockwise();
-        if self.collision(self.x, self.y) || self.out_of_bounds(self.x, self.y) {
+        let kick = self.kick(kicks);
+        if kick.is_none() {
             self.block.rotate_clockwise();
-            return false;
+        } else {
+            self.moved();
+            self.rotated(3, kick);
         }
 
-        true
+        kick
+    }
+
+    pub fn rotate_180(&mut self) -> Option<Kick> {
+        self.block.rotate_clockwise();
+        self.block.rotate_clockwise();
+        let kick = self.kick(&srs::HALF_TURN_KICKS);
+        if kick.is_none() {
+            self.block.rotate_anticlockwise();
+            self.block.rotate_anticlockwise();
+        } else {
+            self.moved();
+            self.rotated(2, kick);
+        }
+
+        kick
+    }
+
+    fn rotated(&mut self, turns: usize, kick: Option<Kick>) {
+        self.last_kick = kick;
+        if let Some(kick) = kick {
+            self.events.push_back(GameEvent::Rotated { turns, kick });
+        }
     }
 
     pub fn slide(&mut self, dx: isize) -> bool {
@@ -182,31 +792,99 @@ impl Game {
         }
 
         self.x += dx;
+        self.moved();
+        self.last_kick = None;
+        self.events.push_back(GameEvent::Moved { dx, dy: 0 });
         true
     }
 
-    pub fn down(&mut self) -> bool {
-        if self.collision(self.x, self.y + 1) || self.out_of_bounds(self.x, self.y + 1) {
+    fn fall_one(&mut self) -> bool {
+        if self.resting() {
             return false;
         }
 
-        self.last_fall = std::time::Instant::now();
-
         self.y += 1;
+        self.last_kick = None;
+        if self.y > self.lowest_y {
+            self.lowest_y = self.y;
+            self.lock_frames = 0;
+            self.lock_resets = 0;
+        }
+        true
+    }
+
+    pub fn down(&mut self) -> bool {
+        if !self.fall_one() {
+            return false;
+        }
+
+        self.fall = 0;
+        self.scoring.soft_drop(1);
+        self.events.push_back(GameEvent::Moved { dx: 0, dy: 1 });
         true
     }
 
+    // Where the active block would land if it were dropped.
+    pub fn ghost(&self) -> (isize, isize) {
+        let mut y = self.y;
+
+        while !self.collision(self.x, y + 1) && !self.out_of_bounds(self.x, y + 1) {
+            y += 1;
+        }
+
+        (self.x, y)
+    }
+
     pub fn drop(&mut self) {
-        loop {
-            if self.collision(self.x, self.y + 1) || self.out_of_bounds(self.x, self.y + 1) {
-                break;
-            }
+        let (_, y) = self.ghost();
+        if y != self.y {
+            self.scoring.hard_drop(y - self.y);
+            self.events.push_back(GameEvent::Moved {
+                dx: 0,
+                dy: y - self.y,
+            });
+            self.y = y;
+            self.last_kick = None;
+        }
+    }
 
-            self.y += 1;
+    // Uses the 3-corner rule: a T which rotated into place is a T-spin if
+    // three of the corners around its centre are filled. It is only a mini
+    // T-spin if one of the two corners it points towards is empty, unless
+    // it got there with the last kick test.
+    pub fn tspin(&self) -> Option<TSpin> {
+        let kick = self.last_kick?;
+        if self.get_piece() != PieceKind::T {
+            return None;
+        }
+
+        let corners = [(0, 0), (2, 0), (2, 2), (0, 2)];
+        let filled: Vec<bool> = corners
+            .iter()
+            .map(|(cx, cy)| {
+                let x = self.x + cx;
+                let y = self.y + cy;
+                x < 0 || x >= self.w || y >= self.h || self.filled(x, y)
+            })
+            .collect();
+
+        if filled.iter().filter(|f| **f).count() < 3 {
+            return None;
+        }
+
+        // The corners either side of where the T points
+        let rotation = self.block.rotation();
+        let front = filled[rotation] && filled[(rotation + 1) % 4];
+
+        if front || kick.test == srs::KICK_TESTS - 1 {
+            Some(TSpin::Full)
+        } else {
+            Some(TSpin::Mini)
         }
     }
 
     pub fn merge(&mut self) -> isize {
+        let tspin = self.tspin();
         let (bw, bh) = self.block.dims();
 
         for by in 0..bh {
@@ -217,9 +895,26 @@ impl Game {
             }
         }
 
+        self.events.push_back(GameEvent::Locked(self.block.kind()));
+
         let count = self.remove_lines();
+        let clear = ClearKind::from_lines(count as usize);
+        if let Some(event) = self.scoring.lock(count as usize, self.level, tspin) {
+            self.stats.combo(event.combo);
+        }
+        self.stats.placed(self.block.kind(), clear, tspin);
+        if let Some(clear) = clear {
+            self.events
+                .push_back(GameEvent::LinesCleared { clear, tspin });
+        }
         if count > 0 {
-            self.score += 1 << count;
+            self.lines += count as usize;
+
+            let level = 1 + self.lines / self.lines_per_level;
+            if level != self.level {
+                self.level = level;
+                self.events.push_back(GameEvent::LevelUp(level));
+            }
         }
 
         count
@@ -228,11 +923,14 @@ impl Game {
     pub fn remove_lines(&mut self) -> isize {
         let (_, bh) = self.block.dims();
 
-        let mut count = 0 as isize;
+        let mut count = 0_isize;
 
         for by in 0..bh {
             let y = by + self.y;
             if self.is_full_line(y) {
+                if (0..self.w).any(|x| self.get(x, y) == Colour::Garbage) {
+                    self.garbage_cleared += 1;
+                }
                 self.move_lines_down(y);
                 count += 1;
             }
@@ -257,9 +955,7 @@ impl Game {
             let mut prev = Colour::Empty;
             for _ in 0..y {
                 index += self.w;
-                let this = self.board[index as usize];
-                self.board[index as usize] = prev;
-                prev = this;
+                std::mem::swap(&mut self.board[index as usize], &mut prev);
             }
         }
     }
@@ -271,15 +967,26 @@ impl Game {
 
 #[cfg(test)]
 mod tests {
+    use super::super::clock::ManualClock;
+    use super::super::mode::{SPRINT_LINES, ULTRA_FRAMES};
     use super::*;
 
+    fn config(w: isize, h: isize, seed: u64) -> Config {
+        Config {
+            width: w,
+            height: h,
+            seed,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn game_new() {
         let cases: Vec<(isize, isize, bool)> = vec![(3, 3, true), (4, 4, false), (10, 10, false)];
 
         for case in cases {
             let (w, h, err) = case;
-            let g = Game::new(w, h);
+            let g = Game::new(&config(w, h, 0));
             match g {
                 Err(_) => assert!(err, "should have returned an error"),
                 Ok(_) => assert!(!err, "should not have returned an error"),
@@ -288,8 +995,856 @@ mod tests {
     }
 
     #[test]
+    fn game_ghost() {
+        let mut g = Game::new(&config(10, 10, 0)).expect("could not create new game");
+
+        g.block.test();
+        g.x = 0;
+        g.y = 0;
+
+        assert_eq!(g.ghost(), (0, 8));
+        assert_eq!(g.display_get(1, 0), Colour::Value(0));
+        assert_eq!(g.display_get(1, 8), Colour::Ghost(0));
+        assert_eq!(g.display_get(0, 9), Colour::Ghost(0));
+        assert_eq!(g.display_get(0, 8), Colour::Empty);
+
+        g.set(0, 5, Colour::Value(1));
+        assert_eq!(g.ghost(), (0, 3));
+
+        g.drop();
+        assert_eq!((g.x, g.y), g.ghost());
+        assert_eq!(g.display_get(1, 3), Colour::Value(0));
+    }
+
+    #[test]
+    fn game_gravity() {
+        let cases: Vec<(&str, usize, isize)> = vec![
+            // A cell every other frame
+            ("0.5", 4, 2),
+            // A cell every frame
+            ("1", 4, 4),
+            // Three cells a frame
+            ("3", 2, 6),
+            // Straight to the floor, and resting there for a frame
+            ("20", 1, 18),
+        ];
+
+        for case in cases {
+            let (gravity, frames, want_y) = case;
+
+            let mut c = config(10, 20, 0);
+            c.gravity = gravity.parse().expect("invalid gravity");
+            let mut g = Game::new(&c).expect("could not create new game");
+            g.spawn(PieceKind::T);
+
+            for _ in 0..frames {
+                g.gravity_frame();
+            }
+            assert_eq!(g.y, want_y, "gravity {}", gravity);
+        }
+    }
+
+    #[test]
+    fn game_frames_due() {
+        let clock = ManualClock::new();
+        let frame = std::time::Duration::from_nanos(FRAME_NANOS);
+
+        let mut g = Game::new(&config(10, 20, 0)).expect("could not create new game");
+        assert_eq!(g.frames_due(&clock), 0);
+
+        // Half a frame is nothing, and a frame and a half only one frame
+        clock.advance(frame / 2);
+        assert_eq!(g.frames_due(&clock), 0);
+        clock.advance(frame);
+        assert_eq!(g.frames_due(&clock), 1);
+
+        // The left over half frame counts towards the next
+        clock.advance(frame / 2);
+        assert_eq!(g.frames_due(&clock), 1);
+
+        clock.advance(frame * 10);
+        assert_eq!(g.frames_due(&clock), 10);
+        assert_eq!(g.frames_due(&clock), 0);
+
+        // A new game starts the clock again
+        clock.advance(frame * 10);
+        g.new_game();
+        assert_eq!(g.frames_due(&clock), 0);
+    }
+
+    #[test]
+    fn game_stats() {
+        let mut g = Game::new(&config(4, 6, 0)).expect("could not create new game");
+        board(
+            &mut g,
+            &[
+                "..##", //
+                "..##", //
+            ],
+        );
+        g.spawn(PieceKind::O);
+        g.step(&[Command::Left, Command::Left, Command::HardDrop]);
+        g.step(&[Command::RotateClockwise]);
+
+        let stats = g.get_stats();
+        assert_eq!(stats.pieces(PieceKind::O), 1);
+        assert_eq!(stats.total_pieces(), 1);
+        assert_eq!(stats.clears(ClearKind::Double), 1);
+        assert_eq!(stats.keys_per_piece(), 4.0);
+        assert_eq!(stats.max_combo(), 0);
+
+        let l = Game::load(&g.save()).expect("could not load game");
+        assert_eq!(l.get_stats().clears(ClearKind::Double), 1);
+        assert_eq!(l.get_stats().keys_per_piece(), 4.0);
+    }
+
+    #[test]
+    fn game_pause() {
+        let clock = ManualClock::new();
+        let frame = std::time::Duration::from_nanos(FRAME_NANOS);
+
+        let mut c = config(10, 20, 0);
+        c.lock_delay = 2;
+        let mut g = Game::new(&c).expect("could not create new game");
+        g.spawn(PieceKind::O);
+        g.drop();
+        g.step(&[]);
+        assert_eq!(g.lock_frames, 1);
+        g.frames_due(&clock);
+        events(&mut g);
+
+        g.pause();
+        assert!(g.is_paused());
+        clock.advance(frame * 100);
+        assert_eq!(g.frames_due(&clock), 0);
+        assert!(!g.step(&[Command::Left]));
+        assert_eq!(g.get_frame(), 1);
+        assert_eq!(g.lock_frames, 1);
+        assert_eq!(g.get_piece(), PieceKind::O);
+
+        // The time spent paused doesn't count
+        g.resume();
+        assert!(!g.is_paused());
+        assert_eq!(g.frames_due(&clock), 0);
+        clock.advance(frame);
+        assert_eq!(g.frames_due(&clock), 1);
+        assert_eq!(events(&mut g), vec![GameEvent::Paused, GameEvent::Resumed]);
+
+        g.step(&[]);
+        assert_eq!(g.get_frame(), 2);
+        assert!(events(&mut g).contains(&GameEvent::Locked(PieceKind::O)));
+    }
+
+    #[test]
+    fn game_step() {
+        let mut g = Game::new(&config(10, 20, 0)).expect("could not create new game");
+        g.spawn(PieceKind::T);
+
+        // Commands apply in order, before gravity
+        assert!(g.step(&[Command::RotateClockwise, Command::Left, Command::Left]));
+        assert_eq!(g.block.rotation(), 1);
+        assert_eq!(g.x, 1);
+
+        assert!(g.step(&[Command::HardDrop]));
+        assert!(g.filled(2, 19));
+        assert!(g.filled(3, 18));
+
+        // A failed command changes nothing
+        g.spawn(PieceKind::O);
+        while g.slide(-1) {}
+        assert!(!g.step(&[Command::Left]));
+    }
+
+    #[test]
+    fn game_step_deterministic() {
+        let script = [
+            Command::Left,
+            Command::RotateClockwise,
+            Command::HardDrop,
+            Command::Hold,
+            Command::Right,
+            Command::Right,
+            Command::SoftDrop,
+            Command::RotateAnticlockwise,
+            Command::HardDrop,
+        ];
+
+        let mut c = config(10, 20, 5);
+        c.gravity = "0.1".parse().expect("invalid gravity");
+        let mut a = Game::new(&c).expect("could not create new game");
+        let mut b = Game::new(&c).expect("could not create new game");
+
+        for i in 0..2000 {
+            let commands = [script[i % script.len()]];
+            let commands: &[Command] = if i % 7 == 0 { &commands } else { &[] };
+            a.step(commands);
+            b.step(commands);
+        }
+
+        assert_eq!(a.board, b.board);
+        assert_eq!(a.get_score(), b.get_score());
+        assert_eq!((a.x, a.y), (b.x, b.y));
+    }
+
+    fn events(g: &mut Game) -> Vec<GameEvent> {
+        let mut events = vec![];
+        while let Some(event) = g.poll_event() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn game_events() {
+        let mut c = config(4, 6, 0);
+        c.lines_per_level = 1;
+        let mut g = Game::new(&c).expect("could not create new game");
+        board(
+            &mut g,
+            &[
+                "..##", //
+                "..##", //
+            ],
+        );
+        g.spawn(PieceKind::O);
+        events(&mut g);
+
+        g.step(&[Command::Left, Command::Left, Command::Hold]);
+        let next = g.get_piece();
+        assert_eq!(
+            events(&mut g),
+            vec![
+                GameEvent::Moved { dx: -1, dy: 0 },
+                GameEvent::Held(PieceKind::O),
+                GameEvent::Spawned(next),
+            ]
+        );
+
+        g.spawn(PieceKind::O);
+        events(&mut g);
+
+        g.step(&[Command::Left, Command::HardDrop]);
+        let next = g.get_piece();
+        assert_eq!(
+            events(&mut g),
+            vec![
+                GameEvent::Moved { dx: -1, dy: 0 },
+                GameEvent::Moved { dx: 0, dy: 4 },
+                GameEvent::Locked(PieceKind::O),
+                GameEvent::LinesCleared {
+                    clear: ClearKind::Double,
+                    tspin: None,
+                },
+                GameEvent::LevelUp(3),
+                GameEvent::Spawned(next),
+            ]
+        );
+    }
+
+    #[test]
+    fn game_events_rotate_and_top_out() {
+        let mut g = Game::new(&config(4, 4, 0)).expect("could not create new game");
+        g.spawn(PieceKind::T);
+        events(&mut g);
+
+        assert!(g.rotate_clockwise().is_some());
+        assert_eq!(
+            events(&mut g),
+            vec![GameEvent::Rotated {
+                turns: 1,
+                kick: Kick {
+                    test: 0,
+                    dx: 0,
+                    dy: 0
+                }
+            }]
+        );
+
+        // Half turns and anticlockwise turns can be told apart
+        let kick = g.rotate_180().expect("T should turn round");
+        assert_eq!(events(&mut g), vec![GameEvent::Rotated { turns: 2, kick }]);
+        let kick = g.rotate_anticlockwise().expect("T should turn back");
+        assert_eq!(events(&mut g), vec![GameEvent::Rotated { turns: 3, kick }]);
+
+        g.set(1, 0, Colour::Value(1));
+        g.spawn(PieceKind::T);
+        assert!(g.is_game_over());
+        assert_eq!(
+            events(&mut g),
+            vec![GameEvent::Spawned(PieceKind::T), GameEvent::TopOut]
+        );
+    }
+
+    #[test]
+    fn game_mode_sprint() {
+        let mut c = config(4, 6, 0);
+        c.mode = Mode::Sprint;
+        let mut g = Game::new(&c).expect("could not create new game");
+        g.lines = SPRINT_LINES - 2;
+
+        g.step(&[]);
+        assert_eq!(g.get_frame(), 1);
+        assert!(!g.is_finished());
+
+        board(
+            &mut g,
+            &[
+                "..##", //
+                "..##", //
+            ],
+        );
+        g.spawn(PieceKind::O);
+        g.step(&[Command::Left, Command::HardDrop]);
+        assert_eq!(g.get_lines(), SPRINT_LINES);
+        assert!(g.is_finished());
+        assert!(g.is_game_over());
+        assert_eq!(g.get_frame(), 2);
+        assert_eq!(events(&mut g).last(), Some(&GameEvent::Finished));
+
+        assert!(!g.step(&[Command::Left]));
+        assert_eq!(g.get_frame(), 2);
+    }
+
+    #[test]
+    fn game_mode_ultra() {
+        let mut c = config(10, 20, 0);
+        c.mode = Mode::Ultra;
+        let mut g = Game::new(&c).expect("could not create new game");
+        g.frame = ULTRA_FRAMES - 2;
+
+        g.step(&[]);
+        assert!(!g.is_finished());
+        g.step(&[]);
+        assert!(g.is_finished());
+        assert_eq!(g.get_frame(), ULTRA_FRAMES);
+    }
+
+    #[test]
+    fn game_mode_zen() {
+        let mut c = config(4, 4, 0);
+        c.mode = Mode::Zen;
+        let mut g = Game::new(&c).expect("could not create new game");
+        events(&mut g);
+
+        g.set(1, 0, Colour::Value(1));
+        g.set(2, 3, Colour::Value(1));
+        g.spawn(PieceKind::T);
+        assert!(!g.is_game_over());
+        assert!(!g.filled(1, 0));
+        assert!(!g.filled(2, 3));
+        assert_eq!(events(&mut g), vec![GameEvent::Spawned(PieceKind::T)]);
+    }
+
+    // Where the hole is in each row of garbage.
+    fn holes(g: &Game) -> Vec<Option<isize>> {
+        (0..g.h)
+            .map(|y| {
+                if (0..g.w).any(|x| g.get(x, y) == Colour::Garbage) {
+                    (0..g.w).find(|x| g.get(*x, y) == Colour::Empty)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn game_add_garbage() {
+        let mut c = config(6, 8, 0);
+        c.messiness = 0;
+        let mut g = Game::new(&c).expect("could not create new game");
+        g.set(0, 7, Colour::Value(1));
+        events(&mut g);
+
+        g.add_garbage(3);
+        assert_eq!(g.get(0, 4), Colour::Value(1));
+        let holes = holes(&g);
+        let hole = holes[5].expect("garbage should have a hole");
+        assert_eq!(holes[6], Some(hole));
+        assert_eq!(holes[7], Some(hole));
+        for x in 0..g.w {
+            if x != hole {
+                assert_eq!(g.get(x, 7), Colour::Garbage);
+            }
+        }
+        assert!(!g.is_game_over());
+        assert_eq!(events(&mut g), vec![GameEvent::Garbage(3)]);
+
+        g.add_garbage(4);
+        assert!(!g.is_game_over());
+        g.add_garbage(1);
+        assert!(g.is_game_over());
+        assert_eq!(
+            events(&mut g),
+            vec![
+                GameEvent::Garbage(4),
+                GameEvent::Garbage(1),
+                GameEvent::TopOut
+            ]
+        );
+    }
+
+    #[test]
+    fn game_add_garbage_messy() {
+        let mut c = config(10, 20, 0);
+        c.messiness = MAX_MESSINESS;
+        let mut g = Game::new(&c).expect("could not create new game");
+
+        g.add_garbage(10);
+        let holes = holes(&g);
+        assert!(
+            holes[10..].windows(2).any(|w| w[0] != w[1]),
+            "holes should move about: {:?}",
+            holes
+        );
+
+        c.messiness = MAX_MESSINESS + 1;
+        assert!(Game::new(&c).is_err());
+    }
+
+    #[test]
+    fn game_add_garbage_lifts_block() {
+        let mut g = Game::new(&config(4, 4, 0)).expect("could not create new game");
+        g.spawn(PieceKind::O);
+        g.drop();
+        assert_eq!(g.y, 2);
+
+        g.add_garbage(1);
+        assert_eq!(g.y, 1);
+        assert!(!g.collision(g.x, g.y));
+    }
+
+    #[test]
+    fn game_mode_dig() {
+        let mut c = config(4, 8, 0);
+        c.mode = Mode::Dig;
+        c.dig_lines = 5;
+        c.messiness = 0;
+        let mut g = Game::new(&c).expect("could not create new game");
+
+        // Only half the board is filled with garbage to start with
+        let holes = holes(&g);
+        assert_eq!(holes[..4], [None, None, None, None]);
+        let hole = holes[4].expect("garbage should have a hole");
+        assert_eq!(g.get_garbage_left(), 5);
+
+        // Clearing garbage brings in more, until there is none left
+        for left in &[1, 0] {
+            g.block.setup_block(PieceKind::I);
+            g.block.rotate_clockwise();
+            g.x = hole - 2;
+            g.y = 0;
+            g.step(&[Command::HardDrop]);
+            assert_eq!(g.get_garbage_left(), *left);
+        }
+
+        assert!(g.is_finished());
+        assert!((0..g.h).all(|y| (0..g.w).all(|x| g.get(x, y) != Colour::Garbage)));
+    }
+
+    #[test]
+    fn game_save_load() {
+        let mut c = config(6, 10, 9);
+        c.mode = Mode::Dig;
+        c.randomizer = RandomizerKind::History;
+        let mut g = Game::new(&c).expect("could not create new game");
+
+        g.step(&[Command::HardDrop]);
+        g.step(&[Command::Hold, Command::Left, Command::RotateClockwise]);
+        g.step(&[Command::SoftDrop]);
+
+        let mut l = Game::load(&g.save()).expect("could not load game");
+        assert_eq!(l.save(), g.save());
+        assert_eq!(l.board, g.board);
+        assert_eq!((l.x, l.y), (g.x, g.y));
+        assert_eq!(l.get_piece(), g.get_piece());
+        assert_eq!(l.block.rotation(), g.block.rotation());
+        assert_eq!(l.get_hold(), g.get_hold());
+        assert_eq!(l.get_score(), g.get_score());
+
+        // Both games carry on exactly the same way
+        for _ in 0..50 {
+            g.step(&[Command::Right, Command::HardDrop]);
+            l.step(&[Command::Right, Command::HardDrop]);
+            if g.is_game_over() {
+                break;
+            }
+        }
+        assert_eq!(l.save(), g.save());
+    }
+
+    #[test]
+    fn game_load_invalid() {
+        let g = Game::new(&config(6, 10, 9)).expect("could not create new game");
+        let s = g.save();
+
+        let cases: Vec<String> = vec![
+            "".to_string(),
+            s.replace("textris-save 2", "textris-save 1"),
+            s.replace("\nx ", "\nxx "),
+            s.replace("width 6", "width 7"),
+            s.replace("piece ", "piece Q"),
+            s.replace("preview_len 3", "preview_len 2"),
+        ];
+
+        for case in cases {
+            assert!(Game::load(&case).is_err(), "loaded {}", case);
+        }
+    }
+
+    #[test]
+    fn game_gravity_lock() {
+        let mut c = config(10, 20, 0);
+        c.gravity = "20".parse().expect("invalid gravity");
+        c.lock_delay = 1;
+        let mut g = Game::new(&c).expect("could not create new game");
+        g.spawn(PieceKind::T);
+
+        assert!(g.gravity_frame());
+        assert!(!g.filled(4, 19));
+        assert!(g.gravity_frame());
+        assert!(g.filled(4, 19));
+    }
+
+    #[test]
+    fn game_lock_delay() {
+        let cases: Vec<(LockReset, usize, u32)> = vec![
+            // Moving does not help with step reset
+            (LockReset::Step, 0, 10),
+            (LockReset::Step, 5, 10),
+            // Each move restarts the delay, until the limit
+            (LockReset::Move, 0, 10),
+            (LockReset::Move, 2, 28),
+            (LockReset::Move, 3, 37),
+            (LockReset::Move, 6, 37),
+        ];
+
+        for case in cases {
+            let (lock_reset, moves, want_frames) = case;
+
+            let mut c = config(10, 20, 0);
+            c.gravity = "20".parse().expect("invalid gravity");
+            c.lock_delay = 10;
+            c.lock_reset = lock_reset;
+            c.move_limit = 3;
+            let mut g = Game::new(&c).expect("could not create new game");
+            g.spawn(PieceKind::T);
+            g.gravity_frame();
+
+            let mut frames = 0;
+            let mut moves_left = moves;
+            while !(0..10).any(|x| g.filled(x, 19)) {
+                g.gravity_frame();
+                frames += 1;
+
+                // Shuffle back and forth just before the block locks
+                if g.lock_frames == 9 && moves_left > 0 {
+                    let dx = if moves_left % 2 == 0 { 1 } else { -1 };
+                    assert!(g.slide(dx));
+                    moves_left -= 1;
+                }
+            }
+
+            assert_eq!(frames, want_frames, "{:?} with {} moves", lock_reset, moves);
+        }
+    }
+
+    #[test]
+    fn game_soft_drop_lock_delay() {
+        let mut c = config(10, 20, 0);
+        c.lock_delay = 10;
+        let mut g = Game::new(&c).expect("could not create new game");
+        g.spawn(PieceKind::T);
+        while g.down() {}
+
+        // Holding soft drop on the stack doesn't lock any sooner
+        for _ in 0..9 {
+            assert!(!g.step(&[Command::SoftDrop]));
+            assert!(!(0..10).any(|x| g.filled(x, 19)));
+        }
+        assert!(g.step(&[Command::SoftDrop]));
+        assert!((0..10).any(|x| g.filled(x, 19)));
+    }
+
+    #[test]
+    fn game_score() {
+        let mut g = Game::new(&config(4, 10, 0)).expect("could not create new game");
+
+        // A soft dropped O that completes two lines
+        for y in 8..10 {
+            g.set(2, y, Colour::Value(1));
+            g.set(3, y, Colour::Value(1));
+        }
+        g.block.setup_block(PieceKind::O);
+        g.x = 0;
+        g.y = 0;
+        assert!(g.down());
+        g.drop();
+        g.merge();
+
+        let event = g.get_last_score().expect("a double should score");
+        assert_eq!(event.clear, Some(ClearKind::Double));
+        assert_eq!(event.drop_points, 1 + 2 * 7);
+        assert_eq!(g.get_score(), 300 + 15);
+    }
+
+    // Builds a board from rows of '#' (filled) and '.' (empty), aligned to
+    // the bottom of the board.
+    fn board(g: &mut Game, rows: &[&str]) {
+        let top = g.h - rows.len() as isize;
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    g.set(x as isize, top + y as isize, Colour::Value(1));
+                }
+            }
+        }
+    }
+
+    fn kick(test: usize) -> Option<Kick> {
+        Some(Kick { test, dx: 0, dy: 0 })
+    }
+
+    #[test]
+    fn game_tspin_double() {
+        let mut g = Game::new(&config(6, 6, 0)).expect("could not create new game");
+        board(
+            &mut g,
+            &[
+                "##....", //
+                "#...##", //
+                "##.###", //
+            ],
+        );
+
+        // A T pointing down into the slot
+        g.block.test();
+        g.block.rotate_clockwise();
+        g.block.rotate_clockwise();
+        g.x = 1;
+        g.y = 3;
+        g.last_kick = kick(0);
+        assert_eq!(g.tspin(), Some(TSpin::Full));
+
+        assert_eq!(g.merge(), 2);
+        let event = g.get_last_score().expect("a t-spin double should score");
+        assert_eq!(event.tspin, Some(TSpin::Full));
+        assert_eq!(event.clear, Some(ClearKind::Double));
+    }
+
+    #[test]
+    fn game_tspin_mini() {
+        let cases: Vec<(usize, Option<TSpin>)> =
+            vec![(0, Some(TSpin::Mini)), (4, Some(TSpin::Full))];
+
+        for case in cases {
+            let (test, want) = case;
+
+            let mut g = Game::new(&config(6, 6, 0)).expect("could not create new game");
+            board(
+                &mut g,
+                &[
+                    "#.....", //
+                    "...##.", //
+                    "#.####", //
+                ],
+            );
+
+            // A T pointing up, with only one of the corners in front filled
+            g.block.test();
+            g.x = 0;
+            g.y = 3;
+            g.last_kick = kick(test);
+            assert_eq!(g.tspin(), want, "kick test {}", test);
+        }
+    }
+
+    #[test]
+    fn game_tspin_needs_rotation() {
+        let mut g = Game::new(&config(6, 6, 0)).expect("could not create new game");
+        board(
+            &mut g,
+            &[
+                "#.#...", //
+                "......", //
+                "#.#...", //
+            ],
+        );
+
+        g.block.test();
+        g.x = 0;
+        g.y = 3;
+        assert_eq!(g.tspin(), None);
+
+        g.last_kick = kick(0);
+        assert_eq!(g.tspin(), Some(TSpin::Full));
+
+        // Only a T counts
+        g.block.setup_block(PieceKind::L);
+        assert_eq!(g.tspin(), None);
+
+        // Moving after rotating does not count
+        g.block.test();
+        g.x = 3;
+        g.y = 0;
+        assert!(g.down());
+        assert_eq!(g.last_kick, None);
+    }
+
+    #[test]
+    fn game_level() {
+        let mut c = config(4, 10, 0);
+        c.lines_per_level = 3;
+        let mut g = Game::new(&c).expect("could not create new game");
+
+        for i in 0..5 {
+            assert_eq!(g.get_lines(), 2 * i);
+            assert_eq!(g.get_level(), 1 + 2 * i / 3);
+
+            // Each O completes two lines
+            for y in 8..10 {
+                g.set(2, y, Colour::Value(1));
+                g.set(3, y, Colour::Value(1));
+            }
+            g.block.setup_block(PieceKind::O);
+            g.x = 0;
+            g.y = 0;
+            g.drop();
+            assert_eq!(g.merge(), 2);
+        }
+    }
+
+    #[test]
+    fn game_spawn() {
+        let mut g = Game::new(&config(10, 20, 0)).expect("could not create new game");
+
+        let cases: Vec<(PieceKind, isize, isize)> = vec![
+            (PieceKind::T, 3, 0),
+            (PieceKind::O, 4, 0),
+            (PieceKind::I, 3, -1),
+        ];
+
+        for case in cases {
+            let (kind, want_x, want_y) = case;
+
+            g.spawn(kind);
+            assert_eq!((g.x, g.y), (want_x, want_y));
+        }
+    }
+
+    #[test]
+    fn game_rotate_wall_kick() {
+        let mut g = Game::new(&config(10, 20, 0)).expect("could not create new game");
+
+        // An upright I against the right wall has to kick left to lie flat
+        g.spawn(PieceKind::I);
+        g.rotate_clockwise().expect("I should rotate in open space");
+        while g.slide(1) {}
+        assert_eq!(g.x, 7);
+
+        let kick = g
+            .rotate_anticlockwise()
+            .expect("I should kick off the wall");
+        assert_eq!(kick.test, 2);
+        assert_eq!((kick.dx, kick.dy), (-1, 0));
+        assert_eq!(g.block.rotation(), 0);
+    }
+
+    #[test]
+    fn game_rotate_180() {
+        let mut g = Game::new(&config(10, 20, 0)).expect("could not create new game");
+
+        g.spawn(PieceKind::T);
+        let kick = g.rotate_180().expect("T should turn in open space");
+        assert_eq!(kick.test, 0);
+        assert_eq!(g.block.rotation(), 2);
+
+        // Flat on the floor, pointing down has to lift it up a row
+        g.spawn(PieceKind::T);
+        g.drop();
+        let y = g.y;
+        let kick = g.rotate_180().expect("T should kick up off the floor");
+        assert_eq!((kick.dx, kick.dy), (0, -1));
+        assert_eq!(g.y, y - 1);
+        assert_eq!(g.block.rotation(), 2);
+    }
+
+    #[test]
+    fn game_rotate_blocked() {
+        let mut g = Game::new(&config(4, 4, 0)).expect("could not create new game");
+
+        g.spawn(PieceKind::I);
+        for y in 1..4 {
+            for x in 0..4 {
+                g.set(x, y, Colour::Value(1));
+            }
+        }
+
+        assert!(g.rotate_clockwise().is_none());
+        assert_eq!(g.block.rotation(), 0);
+        assert_eq!((g.x, g.y), (0, -1));
+    }
+
+    #[test]
+    fn game_hold() {
+        let mut g = Game::new(&config(10, 20, 3)).expect("could not create new game");
+
+        assert!(g.get_hold().is_none());
+
+        let first = g.get_piece();
+        let next = g.get_preview()[0];
+        assert!(g.hold());
+        assert_eq!(g.get_hold(), Some(first));
+        assert_eq!(g.get_piece(), next);
+        assert_eq!(g.y, -g.block.top());
+
+        // Only one hold per block
+        assert!(!g.hold());
+
+        g.drop();
+        g.merge();
+        g.random();
+        let current = g.get_piece();
+        assert!(g.hold());
+        assert_eq!(g.get_piece(), first);
+        assert_eq!(g.get_hold(), Some(current));
+    }
+
+    #[test]
+    fn game_new_preview() {
+        let cases: Vec<(usize, bool)> = vec![(0, true), (1, false), (6, false), (7, true)];
+
+        for case in cases {
+            let (preview, err) = case;
+            let mut c = config(10, 20, 0);
+            c.preview = preview;
+            match Game::new(&c) {
+                Err(_) => assert!(err, "should have returned an error"),
+                Ok(g) => {
+                    assert!(!err, "should not have returned an error");
+                    assert_eq!(g.get_preview().len(), preview);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn game_preview() {
+        let mut g = Game::new(&config(10, 20, 7)).expect("could not create new game");
+
+        for _ in 0..20 {
+            let next = g.get_preview()[0];
+            g.random();
+            assert_eq!(g.get_piece(), next);
+            assert_eq!(g.get_preview().len(), 3);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::type_complexity)]
     fn game_get_set() {
-        let mut game = Game::new(10, 20).ok().expect("game could not be created");
+        let mut game = Game::new(&config(10, 20, 0)).expect("game could not be created");
 
         let cases: Vec<(isize, isize, Colour, Vec<(isize, isize, Colour)>)> = vec![(
             5,
@@ -314,7 +1869,9 @@ mod tests {
 
                 assert!(
                     colour == want_colour,
-                    format!("expected {0} to equal {1}", colour, want_colour)
+                    "expected {0} to equal {1}",
+                    colour,
+                    want_colour
                 );
             }
         }
@@ -322,10 +1879,53 @@ mod tests {
 
     #[test]
     fn game_merge() {
-        let mut g = Game::new(10, 10).ok().expect("could not create new game");
+        let mut g = Game::new(&config(10, 10, 0)).expect("could not create new game");
 
         g.block.test();
 
         g.merge();
     }
+
+    #[test]
+    fn game_seed() {
+        let mut a = Game::new(&config(10, 20, 1234)).expect("could not create new game");
+        let mut b = Game::new(&config(10, 20, 1234)).expect("could not create new game");
+
+        for _ in 0..50 {
+            assert_eq!(a.get_piece(), b.get_piece());
+            a.random();
+            b.random();
+        }
+
+        a.new_game();
+        let mut c = Game::new(&config(10, 20, 1234)).expect("could not create new game");
+        for _ in 0..50 {
+            assert_eq!(a.get_piece(), c.get_piece());
+            a.random();
+            c.random();
+        }
+    }
+
+    #[test]
+    fn game_randomizer() {
+        let kinds = vec![
+            RandomizerKind::Bag,
+            RandomizerKind::History,
+            RandomizerKind::Memoryless,
+        ];
+
+        for kind in kinds {
+            let mut c = config(10, 20, 99);
+            c.randomizer = kind;
+
+            let mut a = Game::new(&c).expect("could not create new game");
+            let mut b = Game::new(&c).expect("could not create new game");
+
+            for _ in 0..50 {
+                assert_eq!(a.get_piece(), b.get_piece());
+                a.random();
+                b.random();
+            }
+        }
+    }
 }
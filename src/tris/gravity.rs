@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+pub const FRAMES_PER_SECOND: u64 = 60;
+pub const FRAME_NANOS: u64 = 1_000_000_000 / FRAMES_PER_SECOND;
+
+// Gravity is measured in 1/256ths of a cell per frame, so 1G (a cell every
+// frame) is 256 and 20G (the whole board at once) is 5120.
+pub const G: u32 = 256;
+pub const MAX_GRAVITY: u32 = 20 * G;
+
+const GUIDELINE_LEVELS: usize = 20;
+
+// The gravity for each level, starting at level 1. Levels past the end of
+// the table keep the last entry's gravity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gravity {
+    table: Vec<u32>,
+}
+
+impl Gravity {
+    // The guideline curve, where a cell takes (0.8 - (level - 1) * 0.007)
+    // ^ (level - 1) seconds to fall.
+    pub fn guideline() -> Self {
+        let table = (0..GUIDELINE_LEVELS)
+            .map(|i| {
+                let seconds = (0.8 - i as f64 * 0.007).powi(i as i32);
+                from_cells_per_frame(1.0 / (seconds * FRAMES_PER_SECOND as f64))
+            })
+            .collect();
+
+        Self { table }
+    }
+
+    // The gravity for each level in 1/256ths of a cell per frame.
+    pub fn table(&self) -> &[u32] {
+        &self.table
+    }
+
+    pub fn from_table(table: Vec<u32>) -> Result<Self, String> {
+        if table.is_empty() || table.iter().any(|g| *g == 0 || *g > MAX_GRAVITY) {
+            Err("gravity must be between 1 and 20G for every level".to_string())
+        } else {
+            Ok(Self { table })
+        }
+    }
+
+    pub fn at(&self, level: usize) -> u32 {
+        let index = level.max(1) - 1;
+        self.table[index.min(self.table.len() - 1)]
+    }
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity::guideline()
+    }
+}
+
+fn from_cells_per_frame(g: f64) -> u32 {
+    ((g * G as f64).round() as u32).clamp(1, MAX_GRAVITY)
+}
+
+// Parses a comma separated list of cells per frame, one per level, such as
+// "0.0167,0.5,1,20".
+impl FromStr for Gravity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut table = vec![];
+
+        for value in s.split(',') {
+            let g = value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("invalid gravity '{}'", value))?;
+            if g.is_nan() || g <= 0.0 {
+                return Err(format!("gravity must be positive, not '{}'", value));
+            }
+            table.push(from_cells_per_frame(g));
+        }
+
+        Ok(Self { table })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravity_guideline() {
+        let g = Gravity::guideline();
+
+        assert_eq!(g.at(1), 4);
+        assert!(g.at(2) > g.at(1));
+        assert_eq!(g.at(GUIDELINE_LEVELS), MAX_GRAVITY);
+        assert_eq!(g.at(100), MAX_GRAVITY);
+        assert_eq!(g.at(0), g.at(1));
+    }
+
+    #[test]
+    fn gravity_from_table() {
+        let g = Gravity::guideline();
+        assert_eq!(Gravity::from_table(g.table().to_vec()), Ok(g));
+
+        assert!(Gravity::from_table(vec![]).is_err());
+        assert!(Gravity::from_table(vec![1, 0]).is_err());
+        assert!(Gravity::from_table(vec![MAX_GRAVITY + 1]).is_err());
+    }
+
+    #[test]
+    fn gravity_from_str() {
+        let cases: Vec<(&str, Option<Vec<u32>>)> = vec![
+            ("1", Some(vec![G])),
+            ("0.5, 1,20", Some(vec![G / 2, G, MAX_GRAVITY])),
+            ("40", Some(vec![MAX_GRAVITY])),
+            ("0.0001", Some(vec![1])),
+            ("0", None),
+            ("fast", None),
+            ("", None),
+        ];
+
+        for case in cases {
+            let (s, want) = case;
+            let got = s.parse::<Gravity>().ok().map(|g| g.table);
+            assert_eq!(got, want, "parsing '{}'", s);
+        }
+    }
+}
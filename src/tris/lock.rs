@@ -0,0 +1,59 @@
+use std::fmt;
+use std::str::FromStr;
+
+// What gives a block resting on the stack more time before it locks.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LockReset {
+    // Only falling to a new lowest row restarts the lock delay.
+    Step,
+    // Any successful slide or rotation also restarts it, up to the move
+    // limit for each row the block reaches.
+    Move,
+}
+
+impl LockReset {
+    pub const ALL: [LockReset; 2] = [LockReset::Step, LockReset::Move];
+}
+
+impl fmt::Display for LockReset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockReset::Step => write!(f, "step"),
+            LockReset::Move => write!(f, "move"),
+        }
+    }
+}
+
+impl FromStr for LockReset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "step" => Ok(LockReset::Step),
+            "move" => Ok(LockReset::Move),
+            _ => Err(format!(
+                "unknown lock reset '{}' (expected step or move)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_reset_from_str() {
+        let cases: Vec<(&str, Option<LockReset>)> = vec![
+            ("step", Some(LockReset::Step)),
+            ("move", Some(LockReset::Move)),
+            ("never", None),
+        ];
+
+        for case in cases {
+            let (s, want) = case;
+            assert_eq!(s.parse::<LockReset>().ok(), want);
+        }
+    }
+}
@@ -2,8 +2,49 @@ pub mod block;
 
 pub use self::block::Block;
 
+pub mod piece;
+pub use self::piece::PieceKind;
+
 pub mod colour;
 pub use self::colour::Colour;
 
 pub mod game;
 pub use self::game::Game;
+
+pub mod rng;
+pub use self::rng::Rng;
+
+pub mod randomizer;
+pub use self::randomizer::{Randomizer, RandomizerKind};
+
+pub mod config;
+pub use self::config::Config;
+
+pub mod srs;
+pub use self::srs::Kick;
+
+pub mod gravity;
+pub use self::gravity::Gravity;
+
+pub mod lock;
+pub use self::lock::LockReset;
+
+pub mod score;
+pub use self::score::{ClearKind, ScoreEvent, Scoring, TSpin};
+
+pub mod clock;
+pub use self::clock::{Clock, SystemClock};
+
+pub mod command;
+pub use self::command::Command;
+
+pub mod event;
+pub use self::event::GameEvent;
+
+pub mod mode;
+pub use self::mode::Mode;
+
+pub mod save;
+
+pub mod stats;
+pub use self::stats::Stats;
@@ -0,0 +1,156 @@
+use super::gravity::FRAMES_PER_SECOND;
+
+use std::fmt;
+use std::str::FromStr;
+
+pub const MARATHON_LINES: usize = 150;
+pub const MARATHON_LEVEL_CAP: usize = 15;
+pub const SPRINT_LINES: usize = 40;
+pub const ULTRA_FRAMES: u64 = 2 * 60 * FRAMES_PER_SECOND;
+
+// What the player is trying to do, and when the game is over.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    // Clear 150 lines, or get through the last level.
+    Marathon,
+    // Clear 40 lines as fast as possible.
+    Sprint,
+    // Score as much as possible in 2 minutes.
+    Ultra,
+    // Play forever: topping out just clears the board.
+    Zen,
+    // Dig through a set number of garbage lines as fast as possible.
+    Dig,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 5] = [
+        Mode::Marathon,
+        Mode::Sprint,
+        Mode::Ultra,
+        Mode::Zen,
+        Mode::Dig,
+    ];
+
+    // The number of lines that finishes the game.
+    pub fn line_goal(self) -> Option<usize> {
+        match self {
+            Mode::Marathon => Some(MARATHON_LINES),
+            Mode::Sprint => Some(SPRINT_LINES),
+            Mode::Ultra | Mode::Zen | Mode::Dig => None,
+        }
+    }
+
+    // The level which finishes the game on being reached.
+    pub fn level_cap(self) -> Option<usize> {
+        match self {
+            Mode::Marathon => Some(MARATHON_LEVEL_CAP + 1),
+            _ => None,
+        }
+    }
+
+    // The number of frames the game lasts.
+    pub fn time_limit(self) -> Option<u64> {
+        match self {
+            Mode::Ultra => Some(ULTRA_FRAMES),
+            _ => None,
+        }
+    }
+
+    pub fn can_top_out(self) -> bool {
+        self != Mode::Zen
+    }
+
+    pub fn has_garbage(self) -> bool {
+        self == Mode::Dig
+    }
+
+    pub fn finished(self, lines: usize, level: usize, frame: u64, garbage_left: usize) -> bool {
+        self.line_goal().is_some_and(|goal| lines >= goal)
+            || self.level_cap().is_some_and(|cap| level >= cap)
+            || self.time_limit().is_some_and(|limit| frame >= limit)
+            || (self.has_garbage() && garbage_left == 0)
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Marathon => write!(f, "Marathon"),
+            Mode::Sprint => write!(f, "Sprint"),
+            Mode::Ultra => write!(f, "Ultra"),
+            Mode::Zen => write!(f, "Zen"),
+            Mode::Dig => write!(f, "Dig"),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "marathon" => Ok(Mode::Marathon),
+            "sprint" => Ok(Mode::Sprint),
+            "ultra" => Ok(Mode::Ultra),
+            "zen" => Ok(Mode::Zen),
+            "dig" => Ok(Mode::Dig),
+            _ => Err(format!(
+                "unknown mode '{}' (expected marathon, sprint, ultra, zen or dig)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_from_str() {
+        let cases: Vec<(&str, Option<Mode>)> = vec![
+            ("marathon", Some(Mode::Marathon)),
+            ("sprint", Some(Mode::Sprint)),
+            ("ultra", Some(Mode::Ultra)),
+            ("zen", Some(Mode::Zen)),
+            ("dig", Some(Mode::Dig)),
+            ("endless", None),
+        ];
+
+        for case in cases {
+            let (s, want) = case;
+            assert_eq!(s.parse::<Mode>().ok(), want);
+        }
+    }
+
+    #[test]
+    fn mode_finished() {
+        let cases: Vec<(Mode, usize, usize, u64, usize, bool)> = vec![
+            (Mode::Marathon, 149, 15, 1_000_000, 0, false),
+            (Mode::Marathon, 150, 16, 0, 0, true),
+            (Mode::Marathon, 20, 16, 0, 0, true),
+            (Mode::Sprint, 39, 4, 1_000_000, 0, false),
+            (Mode::Sprint, 41, 5, 0, 0, true),
+            (Mode::Ultra, 500, 50, ULTRA_FRAMES - 1, 0, false),
+            (Mode::Ultra, 0, 1, ULTRA_FRAMES, 0, true),
+            (Mode::Zen, 500, 50, 1_000_000, 0, false),
+            (Mode::Dig, 500, 50, 1_000_000, 1, false),
+            (Mode::Dig, 0, 1, 0, 0, true),
+        ];
+
+        for case in cases {
+            let (mode, lines, level, frame, garbage_left, want) = case;
+            assert_eq!(
+                mode.finished(lines, level, frame, garbage_left),
+                want,
+                "{} at {} lines, level {}, frame {}, {} garbage left",
+                mode,
+                lines,
+                level,
+                frame,
+                garbage_left
+            );
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use super::Colour;
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    I,
+    O,
+    T,
+    S,
+    Z,
+    J,
+    L,
+}
+
+impl PieceKind {
+    pub const ALL: [PieceKind; 7] = [
+        PieceKind::I,
+        PieceKind::O,
+        PieceKind::T,
+        PieceKind::S,
+        PieceKind::Z,
+        PieceKind::J,
+        PieceKind::L,
+    ];
+
+    pub fn colour(self) -> Colour {
+        match self {
+            PieceKind::T => Colour::Value(0),
+            PieceKind::L => Colour::Value(1),
+            PieceKind::J => Colour::Value(2),
+            PieceKind::O => Colour::Value(3),
+            PieceKind::Z => Colour::Value(4),
+            PieceKind::S => Colour::Value(5),
+            PieceKind::I => Colour::Value(6),
+        }
+    }
+}
+
+impl fmt::Display for PieceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PieceKind::I => write!(f, "I"),
+            PieceKind::O => write!(f, "O"),
+            PieceKind::T => write!(f, "T"),
+            PieceKind::S => write!(f, "S"),
+            PieceKind::Z => write!(f, "Z"),
+            PieceKind::J => write!(f, "J"),
+            PieceKind::L => write!(f, "L"),
+        }
+    }
+}
+
+impl FromStr for PieceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PieceKind::ALL
+            .iter()
+            .find(|kind| kind.to_string() == s)
+            .cloned()
+            .ok_or_else(|| format!("unknown piece '{}'", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piece_kind_from_str() {
+        for kind in PieceKind::ALL.iter() {
+            assert_eq!(kind.to_string().parse::<PieceKind>(), Ok(*kind));
+        }
+        assert!("X".parse::<PieceKind>().is_err());
+    }
+
+    #[test]
+    fn piece_kind_colour() {
+        let mut colours: Vec<Colour> = vec![];
+
+        for kind in PieceKind::ALL.iter() {
+            let colour = kind.colour();
+            assert!(!colours.contains(&colour), "{} shares a colour", kind);
+            colours.push(colour);
+        }
+    }
+}
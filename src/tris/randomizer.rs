@@ -0,0 +1,283 @@
+use super::PieceKind;
+use super::Rng;
+
+use std::fmt;
+use std::str::FromStr;
+
+// Pieces which make a poor opening, as in TGM.
+const HISTORY_BAD_FIRST: [PieceKind; 3] = [PieceKind::O, PieceKind::Z, PieceKind::S];
+// The history starts out full of Z and S so that neither comes up early.
+const HISTORY_START: [PieceKind; 4] = [PieceKind::Z, PieceKind::S, PieceKind::Z, PieceKind::S];
+const HISTORY_ROLLS: usize = 6;
+
+pub trait Randomizer {
+    fn next(&mut self, rng: &mut Rng) -> PieceKind;
+
+    // What the randomizer remembers between pieces, for saving.
+    fn state(&self) -> Vec<PieceKind>;
+
+    fn restore(&mut self, state: &[PieceKind]) -> Result<(), String>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RandomizerKind {
+    Bag,
+    History,
+    Memoryless,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 3] = [
+        RandomizerKind::Bag,
+        RandomizerKind::History,
+        RandomizerKind::Memoryless,
+    ];
+
+    pub fn create(self) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::Bag => Box::new(Bag::new()),
+            RandomizerKind::History => Box::new(History::new()),
+            RandomizerKind::Memoryless => Box::new(Memoryless {}),
+        }
+    }
+}
+
+impl fmt::Display for RandomizerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RandomizerKind::Bag => write!(f, "bag"),
+            RandomizerKind::History => write!(f, "history"),
+            RandomizerKind::Memoryless => write!(f, "memoryless"),
+        }
+    }
+}
+
+impl FromStr for RandomizerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bag" => Ok(RandomizerKind::Bag),
+            "history" => Ok(RandomizerKind::History),
+            "memoryless" => Ok(RandomizerKind::Memoryless),
+            _ => Err(format!(
+                "unknown randomizer '{}' (expected bag, history or memoryless)",
+                s
+            )),
+        }
+    }
+}
+
+fn roll(rng: &mut Rng) -> PieceKind {
+    PieceKind::ALL[rng.below(PieceKind::ALL.len())]
+}
+
+// Every piece is equally likely every time.
+pub struct Memoryless {}
+
+impl Randomizer for Memoryless {
+    fn next(&mut self, rng: &mut Rng) -> PieceKind {
+        roll(rng)
+    }
+
+    fn state(&self) -> Vec<PieceKind> {
+        vec![]
+    }
+
+    fn restore(&mut self, state: &[PieceKind]) -> Result<(), String> {
+        if state.is_empty() {
+            Ok(())
+        } else {
+            Err("memoryless randomizer has no state".to_string())
+        }
+    }
+}
+
+// Deals out a shuffled bag of all seven pieces before refilling.
+pub struct Bag {
+    bag: Vec<PieceKind>,
+}
+
+impl Bag {
+    pub fn new() -> Self {
+        Self { bag: vec![] }
+    }
+
+    fn refill(&mut self, rng: &mut Rng) {
+        self.bag = PieceKind::ALL.to_vec();
+
+        for i in (1..self.bag.len()).rev() {
+            let j = rng.below(i + 1);
+            self.bag.swap(i, j);
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self, rng: &mut Rng) -> PieceKind {
+        if self.bag.is_empty() {
+            self.refill(rng);
+        }
+
+        self.bag.pop().unwrap()
+    }
+
+    fn state(&self) -> Vec<PieceKind> {
+        self.bag.clone()
+    }
+
+    fn restore(&mut self, state: &[PieceKind]) -> Result<(), String> {
+        if state.len() > PieceKind::ALL.len() {
+            return Err("too many pieces in the bag".to_string());
+        }
+
+        self.bag = state.to_vec();
+        Ok(())
+    }
+}
+
+// Rerolls a few times to avoid any of the last four pieces, as in TGM.
+pub struct History {
+    history: [PieceKind; 4],
+    first: bool,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            history: HISTORY_START,
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for History {
+    fn next(&mut self, rng: &mut Rng) -> PieceKind {
+        let mut kind = roll(rng);
+
+        if self.first {
+            while HISTORY_BAD_FIRST.contains(&kind) {
+                kind = roll(rng);
+            }
+            self.first = false;
+        } else {
+            for _ in 1..HISTORY_ROLLS {
+                if !self.history.contains(&kind) {
+                    break;
+                }
+                kind = roll(rng);
+            }
+        }
+
+        self.history.rotate_left(1);
+        self.history[3] = kind;
+
+        kind
+    }
+
+    // Nothing is remembered until the first piece has been dealt.
+    fn state(&self) -> Vec<PieceKind> {
+        if self.first {
+            vec![]
+        } else {
+            self.history.to_vec()
+        }
+    }
+
+    fn restore(&mut self, state: &[PieceKind]) -> Result<(), String> {
+        if state.is_empty() {
+            *self = Self::new();
+        } else if state.len() == self.history.len() {
+            self.history.copy_from_slice(state);
+            self.first = false;
+        } else {
+            return Err("wrong number of pieces in the history".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn randomizer_from_str() {
+        let cases: Vec<(&str, Option<RandomizerKind>)> = vec![
+            ("bag", Some(RandomizerKind::Bag)),
+            ("history", Some(RandomizerKind::History)),
+            ("memoryless", Some(RandomizerKind::Memoryless)),
+            ("other", None),
+        ];
+
+        for case in cases {
+            let (s, want) = case;
+            assert_eq!(s.parse::<RandomizerKind>().ok(), want);
+        }
+    }
+
+    #[test]
+    fn randomizer_restore() {
+        for kind in RandomizerKind::ALL.iter() {
+            assert_eq!(kind.to_string().parse::<RandomizerKind>(), Ok(*kind));
+
+            let mut rng = Rng::new(3);
+            let mut a = kind.create();
+            for _ in 0..10 {
+                a.next(&mut rng);
+            }
+
+            let mut b = kind.create();
+            b.restore(&a.state()).expect("could not restore state");
+            let mut brng = rng.clone();
+            for _ in 0..20 {
+                assert_eq!(a.next(&mut rng), b.next(&mut brng), "{}", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn randomizer_bag() {
+        let mut rng = Rng::new(0);
+        let mut r = Bag::new();
+
+        for _ in 0..10 {
+            let seen: Vec<PieceKind> = (0..PieceKind::ALL.len())
+                .map(|_| r.next(&mut rng))
+                .collect();
+            for kind in PieceKind::ALL.iter() {
+                assert!(seen.contains(kind), "{} missing from bag", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn randomizer_history() {
+        let mut rng = Rng::new(0);
+        let mut r = History::new();
+
+        let first = r.next(&mut rng);
+        assert!(!HISTORY_BAD_FIRST.contains(&first));
+
+        let mut seen: Vec<PieceKind> = vec![];
+        for _ in 0..1000 {
+            let kind = r.next(&mut rng);
+            seen.push(kind);
+        }
+        for kind in PieceKind::ALL.iter() {
+            assert!(seen.contains(kind), "history never dealt {}", kind);
+        }
+    }
+
+    #[test]
+    fn randomizer_memoryless() {
+        let mut rng = Rng::new(0);
+        let mut r = Memoryless {};
+
+        let seen: Vec<PieceKind> = (0..1000).map(|_| r.next(&mut rng)).collect();
+        for kind in PieceKind::ALL.iter() {
+            assert!(seen.contains(kind), "memoryless never dealt {}", kind);
+        }
+    }
+}
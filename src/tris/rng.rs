@@ -0,0 +1,92 @@
+// A small xorshift64* generator. It is deliberately self-contained so that
+// the piece sequence for a given seed never changes with dependency upgrades.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Run the seed through splitmix64 so that small or zero seeds still
+        // give a well-mixed, non-zero starting state.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Self {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    // The raw state, which from_state picks up from exactly.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn from_state(state: u64) -> Result<Self, String> {
+        if state == 0 {
+            Err("the random state can't be zero".to_string())
+        } else {
+            Ok(Self { state })
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_same_seed() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn rng_different_seed() {
+        let mut a = Rng::new(1);
+        let mut b = Rng::new(2);
+
+        let same = (0..100).filter(|_| a.next_u64() == b.next_u64()).count();
+        assert!(same < 100, "different seeds produced the same sequence");
+    }
+
+    #[test]
+    fn rng_state() {
+        let mut a = Rng::new(7);
+        a.next_u64();
+
+        let mut b = Rng::from_state(a.state()).expect("could not restore state");
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert!(Rng::from_state(0).is_err());
+    }
+
+    #[test]
+    fn rng_below() {
+        let mut r = Rng::new(0);
+
+        for _ in 0..1000 {
+            assert!(r.below(7) < 7);
+        }
+    }
+}
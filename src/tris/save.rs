@@ -0,0 +1,188 @@
+use super::Colour;
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+// Bump this whenever a field is added, removed or changes meaning, so that
+// old saves are turned away rather than misread.
+pub const SAVE_VERSION: u32 = 2;
+const SAVE_HEADER: &str = "textris-save";
+const NONE: &str = "-";
+
+// Saves are plain text: a header line with the version, and then one
+// "key value" line per field. Lists are separated by spaces.
+pub struct SaveWriter {
+    out: String,
+}
+
+impl SaveWriter {
+    pub fn new() -> Self {
+        Self {
+            out: format!("{} {}\n", SAVE_HEADER, SAVE_VERSION),
+        }
+    }
+
+    // Carries on writing after the end of an existing save.
+    pub fn append(out: String) -> Self {
+        Self { out }
+    }
+
+    pub fn field<T: Display>(&mut self, key: &str, value: T) {
+        self.out += &format!("{} {}\n", key, value);
+    }
+
+    pub fn optional<T: Display>(&mut self, key: &str, value: Option<T>) {
+        match value {
+            Some(value) => self.field(key, value),
+            None => self.field(key, NONE),
+        }
+    }
+
+    pub fn list<T: Display>(&mut self, key: &str, values: &[T]) {
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        self.field(key, values.join(" "));
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+pub struct SaveReader<'a> {
+    fields: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> SaveReader<'a> {
+    pub fn new(s: &'a str) -> Result<Self, String> {
+        let mut lines = s.lines();
+
+        let header = lines.next().unwrap_or("");
+        let version = match header.split_once(' ') {
+            Some((SAVE_HEADER, version)) => version
+                .parse::<u32>()
+                .map_err(|_| format!("invalid save version '{}'", version))?,
+            _ => return Err("not a textris save".to_string()),
+        };
+        if version != SAVE_VERSION {
+            return Err(format!("unsupported save version {}", version));
+        }
+
+        let mut fields = HashMap::new();
+        for line in lines.filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            fields.insert(key, value);
+        }
+
+        Ok(Self { fields })
+    }
+
+    fn raw(&self, key: &str) -> Result<&'a str, String> {
+        self.fields
+            .get(key)
+            .cloned()
+            .ok_or_else(|| format!("save is missing '{}'", key))
+    }
+
+    pub fn field<T: FromStr>(&self, key: &str) -> Result<T, String> {
+        let value = self.raw(key)?;
+        value
+            .parse::<T>()
+            .map_err(|_| format!("invalid {} '{}' in save", key, value))
+    }
+
+    pub fn optional<T: FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        if self.raw(key)? == NONE {
+            Ok(None)
+        } else {
+            self.field(key).map(Some)
+        }
+    }
+
+    pub fn list<T: FromStr>(&self, key: &str) -> Result<Vec<T>, String> {
+        self.raw(key)?
+            .split_whitespace()
+            .map(|value| {
+                value
+                    .parse::<T>()
+                    .map_err(|_| format!("invalid {} '{}' in save", key, value))
+            })
+            .collect()
+    }
+}
+
+// Board cells are saved a row at a time, one character each.
+pub fn cell(colour: Colour) -> char {
+    match colour {
+        Colour::Value(i) if i < 10 => std::char::from_digit(i as u32, 10).unwrap(),
+        Colour::Garbage => 'G',
+        _ => '.',
+    }
+}
+
+pub fn colour(c: char) -> Result<Colour, String> {
+    match c {
+        '.' => Ok(Colour::Empty),
+        'G' => Ok(Colour::Garbage),
+        _ => c
+            .to_digit(10)
+            .map(|i| Colour::Value(i as usize))
+            .ok_or_else(|| format!("invalid board cell '{}' in save", c)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_round_trip() {
+        let mut w = SaveWriter::new();
+        w.field("width", 10);
+        w.optional("hold", Some('T'));
+        w.optional::<char>("kick", None);
+        w.list("preview", &[1, 2, 3]);
+        w.list::<u32>("empty", &[]);
+        let s = w.finish();
+
+        let r = SaveReader::new(&s).expect("could not read save");
+        assert_eq!(r.field::<isize>("width"), Ok(10));
+        assert_eq!(r.optional::<char>("hold"), Ok(Some('T')));
+        assert_eq!(r.optional::<char>("kick"), Ok(None));
+        assert_eq!(r.list::<u32>("preview"), Ok(vec![1, 2, 3]));
+        assert_eq!(r.list::<u32>("empty"), Ok(vec![]));
+        assert!(r.field::<isize>("height").is_err());
+        assert!(r.field::<bool>("width").is_err());
+    }
+
+    #[test]
+    fn save_version() {
+        let cases: Vec<(&str, bool)> = vec![
+            ("textris-save 2\n", true),
+            ("textris-save 1\n", false),
+            ("textris-save one\n", false),
+            ("something else\n", false),
+            ("", false),
+        ];
+
+        for case in cases {
+            let (s, ok) = case;
+            assert_eq!(SaveReader::new(s).is_ok(), ok, "reading '{}'", s);
+        }
+    }
+
+    #[test]
+    fn save_cells() {
+        for c in [
+            Colour::Empty,
+            Colour::Value(0),
+            Colour::Value(6),
+            Colour::Garbage,
+        ]
+        .iter()
+        {
+            assert_eq!(colour(cell(*c)), Ok(*c));
+        }
+        assert!(colour('x').is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use super::save::{SaveReader, SaveWriter};
+
+use std::fmt;
+
+const SOFT_DROP_POINTS: isize = 1;
+const HARD_DROP_POINTS: isize = 2;
+const COMBO_POINTS: isize = 50;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClearKind {
+    Single,
+    Double,
+    Triple,
+    Tetris,
+}
+
+impl ClearKind {
+    pub const ALL: [ClearKind; 4] = [
+        ClearKind::Single,
+        ClearKind::Double,
+        ClearKind::Triple,
+        ClearKind::Tetris,
+    ];
+
+    pub fn from_lines(lines: usize) -> Option<Self> {
+        match lines {
+            1 => Some(ClearKind::Single),
+            2 => Some(ClearKind::Double),
+            3 => Some(ClearKind::Triple),
+            4 => Some(ClearKind::Tetris),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TSpin {
+    Mini,
+    Full,
+}
+
+impl fmt::Display for TSpin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TSpin::Mini => write!(f, "T-Spin Mini"),
+            TSpin::Full => write!(f, "T-Spin"),
+        }
+    }
+}
+
+fn clear_points(clear: Option<ClearKind>, tspin: Option<TSpin>) -> isize {
+    match (tspin, clear) {
+        (None, None) => 0,
+        (None, Some(ClearKind::Single)) => 100,
+        (None, Some(ClearKind::Double)) => 300,
+        (None, Some(ClearKind::Triple)) => 500,
+        (None, Some(ClearKind::Tetris)) => 800,
+        (Some(TSpin::Mini), None) => 100,
+        (Some(TSpin::Mini), Some(ClearKind::Single)) => 200,
+        (Some(TSpin::Mini), Some(_)) => 400,
+        (Some(TSpin::Full), None) => 400,
+        (Some(TSpin::Full), Some(ClearKind::Single)) => 800,
+        (Some(TSpin::Full), Some(ClearKind::Double)) => 1200,
+        (Some(TSpin::Full), Some(_)) => 1600,
+    }
+}
+
+// Difficult clears keep a back-to-back chain going.
+fn difficult(clear: ClearKind, tspin: Option<TSpin>) -> bool {
+    clear == ClearKind::Tetris || tspin.is_some()
+}
+
+impl fmt::Display for ClearKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClearKind::Single => write!(f, "Single"),
+            ClearKind::Double => write!(f, "Double"),
+            ClearKind::Triple => write!(f, "Triple"),
+            ClearKind::Tetris => write!(f, "Tetris"),
+        }
+    }
+}
+
+// The breakdown of the points scored by a block that cleared lines or was
+// spun into place.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScoreEvent {
+    pub clear: Option<ClearKind>,
+    pub tspin: Option<TSpin>,
+    pub level: usize,
+    pub clear_points: isize,
+    pub back_to_back: bool,
+    pub combo: usize,
+    pub combo_points: isize,
+    pub drop_points: isize,
+}
+
+impl ScoreEvent {
+    pub fn total(&self) -> isize {
+        self.clear_points + self.combo_points + self.drop_points
+    }
+}
+
+impl fmt::Display for ScoreEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.back_to_back {
+            write!(f, "B2B ")?;
+        }
+        match (self.tspin, self.clear) {
+            (Some(tspin), Some(clear)) => write!(f, "{} {}", tspin, clear)?,
+            (Some(tspin), None) => write!(f, "{}", tspin)?,
+            (None, Some(clear)) => write!(f, "{}", clear)?,
+            (None, None) => (),
+        }
+        if self.combo > 0 {
+            write!(f, " Combo {}", self.combo)?;
+        }
+        write!(f, " +{}", self.total())
+    }
+}
+
+pub struct Scoring {
+    score: isize,
+    drop_points: isize,
+    // The number of blocks in a row that have cleared lines, less one
+    combo: Option<usize>,
+    back_to_back: bool,
+    last: Option<ScoreEvent>,
+}
+
+impl Scoring {
+    pub fn new() -> Self {
+        Self {
+            score: 0,
+            drop_points: 0,
+            combo: None,
+            back_to_back: false,
+            last: None,
+        }
+    }
+
+    pub fn score(&self) -> isize {
+        self.score
+    }
+
+    // The breakdown of the last score isn't saved, only what affects the
+    // points still to come.
+    pub fn save(&self, w: &mut SaveWriter) {
+        w.field("score", self.score);
+        w.field("drop_points", self.drop_points);
+        w.optional("combo", self.combo);
+        w.field("back_to_back", self.back_to_back);
+    }
+
+    pub fn load(r: &SaveReader) -> Result<Self, String> {
+        Ok(Self {
+            score: r.field("score")?,
+            drop_points: r.field("drop_points")?,
+            combo: r.optional("combo")?,
+            back_to_back: r.field("back_to_back")?,
+            last: None,
+        })
+    }
+
+    pub fn last(&self) -> Option<ScoreEvent> {
+        self.last
+    }
+
+    pub fn soft_drop(&mut self, cells: isize) {
+        self.drop_points += cells * SOFT_DROP_POINTS;
+        self.score += cells * SOFT_DROP_POINTS;
+    }
+
+    pub fn hard_drop(&mut self, cells: isize) {
+        self.drop_points += cells * HARD_DROP_POINTS;
+        self.score += cells * HARD_DROP_POINTS;
+    }
+
+    // Scores a block locking into place, having cleared the given number of
+    // lines at the given level. A T-spin which clears nothing still scores,
+    // but neither continues nor breaks a combo or back-to-back chain.
+    pub fn lock(&mut self, lines: usize, level: usize, tspin: Option<TSpin>) -> Option<ScoreEvent> {
+        let drop_points = self.drop_points;
+        self.drop_points = 0;
+
+        let clear = ClearKind::from_lines(lines);
+        if clear.is_none() && tspin.is_none() {
+            self.combo = None;
+            return None;
+        }
+
+        let level = level.max(1);
+        let mut clear_points = clear_points(clear, tspin) * level as isize;
+
+        let mut back_to_back = false;
+        let mut combo = 0;
+        let mut combo_points = 0;
+
+        if let Some(clear) = clear {
+            back_to_back = difficult(clear, tspin) && self.back_to_back;
+            self.back_to_back = difficult(clear, tspin);
+            if back_to_back {
+                clear_points = clear_points * 3 / 2;
+            }
+
+            combo = self.combo.map_or(0, |combo| combo + 1);
+            self.combo = Some(combo);
+            combo_points = COMBO_POINTS * combo as isize * level as isize;
+        }
+
+        let event = ScoreEvent {
+            clear,
+            tspin,
+            level,
+            clear_points,
+            back_to_back,
+            combo,
+            combo_points,
+            drop_points,
+        };
+
+        self.score += clear_points + combo_points;
+        self.last = Some(event);
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoring_clears() {
+        let cases: Vec<(usize, usize, isize)> = vec![
+            (0, 1, 0),
+            (1, 1, 100),
+            (2, 1, 300),
+            (3, 1, 500),
+            (4, 1, 800),
+            (1, 3, 300),
+            (4, 2, 1600),
+        ];
+
+        for case in cases {
+            let (lines, level, want) = case;
+
+            let mut s = Scoring::new();
+            s.lock(lines, level, None);
+            assert_eq!(s.score(), want, "{} lines at level {}", lines, level);
+        }
+    }
+
+    #[test]
+    fn scoring_drops() {
+        let mut s = Scoring::new();
+
+        s.soft_drop(3);
+        s.hard_drop(10);
+        assert_eq!(s.score(), 23);
+
+        let event = s.lock(1, 1, None).expect("a single should score");
+        assert_eq!(event.drop_points, 23);
+        assert_eq!(event.total(), 123);
+        assert_eq!(s.score(), 123);
+
+        s.hard_drop(1);
+        let event = s.lock(1, 1, None).expect("a single should score");
+        assert_eq!(event.drop_points, 2);
+    }
+
+    #[test]
+    fn scoring_combo() {
+        let mut s = Scoring::new();
+
+        let cases: Vec<(usize, Option<TSpin>, Option<usize>, isize)> = vec![
+            (1, None, Some(0), 0),
+            (1, None, Some(1), 50),
+            // A T-spin zero leaves the combo going
+            (0, Some(TSpin::Full), Some(0), 0),
+            (2, None, Some(2), 100),
+            (0, None, None, 0),
+            (1, None, Some(0), 0),
+        ];
+
+        for case in cases {
+            let (lines, tspin, want_combo, want_points) = case;
+
+            let event = s.lock(lines, 1, tspin);
+            assert_eq!(event.map(|e| e.combo), want_combo);
+            assert_eq!(event.map_or(0, |e| e.combo_points), want_points);
+        }
+    }
+
+    #[test]
+    fn scoring_back_to_back() {
+        let mut s = Scoring::new();
+
+        let cases: Vec<(usize, bool, isize)> = vec![
+            (4, false, 800),
+            (0, false, 0),
+            (4, true, 1200),
+            (4, true, 1200),
+            (1, false, 100),
+            (4, false, 800),
+        ];
+
+        for case in cases {
+            let (lines, want_b2b, want_points) = case;
+
+            let event = s.lock(lines, 1, None);
+            assert_eq!(event.is_some_and(|e| e.back_to_back), want_b2b);
+            assert_eq!(event.map_or(0, |e| e.clear_points), want_points);
+        }
+    }
+
+    #[test]
+    fn score_event_display() {
+        let mut s = Scoring::new();
+
+        s.lock(4, 1, None);
+        let event = s.lock(4, 1, None).expect("a tetris should score");
+        assert_eq!(format!("{}", event), "B2B Tetris Combo 1 +1250");
+    }
+
+    #[test]
+    fn scoring_tspins() {
+        let cases: Vec<(usize, Option<TSpin>, isize)> = vec![
+            (0, Some(TSpin::Mini), 100),
+            (1, Some(TSpin::Mini), 200),
+            (2, Some(TSpin::Mini), 400),
+            (0, Some(TSpin::Full), 400),
+            (1, Some(TSpin::Full), 800),
+            (2, Some(TSpin::Full), 1200),
+            (3, Some(TSpin::Full), 1600),
+        ];
+
+        for case in cases {
+            let (lines, tspin, want) = case;
+
+            let mut s = Scoring::new();
+            s.lock(lines, 1, tspin);
+            assert_eq!(s.score(), want, "{} lines with {:?}", lines, tspin);
+        }
+    }
+
+    #[test]
+    fn scoring_tspin_back_to_back() {
+        let mut s = Scoring::new();
+
+        let cases: Vec<(usize, Option<TSpin>, bool, isize)> = vec![
+            (4, None, false, 800),
+            (2, Some(TSpin::Full), true, 1800),
+            (0, Some(TSpin::Full), false, 400),
+            (1, Some(TSpin::Mini), true, 300),
+            (1, None, false, 100),
+        ];
+
+        for case in cases {
+            let (lines, tspin, want_b2b, want_points) = case;
+
+            let event = s.lock(lines, 1, tspin).expect("should score");
+            assert_eq!(event.back_to_back, want_b2b);
+            assert_eq!(event.clear_points, want_points);
+        }
+    }
+
+    #[test]
+    fn score_event_display_tspin() {
+        let cases: Vec<(usize, TSpin, &str)> = vec![
+            (0, TSpin::Full, "T-Spin +400"),
+            (2, TSpin::Full, "T-Spin Double +1200"),
+            (1, TSpin::Mini, "T-Spin Mini Single +200"),
+        ];
+
+        for case in cases {
+            let (lines, tspin, want) = case;
+
+            let mut s = Scoring::new();
+            let event = s.lock(lines, 1, Some(tspin)).expect("should score");
+            assert_eq!(format!("{}", event), want);
+        }
+    }
+}
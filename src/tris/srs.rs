@@ -0,0 +1,91 @@
+// Super Rotation System wall kick tables.
+//
+// Offsets are (dx, dy) with y pointing down the board, and are tried in
+// order until one fits. Each table has one row per transition, in the order
+// 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L.
+
+use super::PieceKind;
+
+pub const KICK_TESTS: usize = 5;
+
+type KickTable = [[(isize, isize); KICK_TESTS]; 8];
+
+const JLSTZ_KICKS: KickTable = [
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+];
+
+const I_KICKS: KickTable = [
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+];
+
+const O_KICKS: [(isize, isize); 1] = [(0, 0)];
+
+// SRS has no half turns, so these just try in place, then up a row, then
+// either side.
+pub const HALF_TURN_KICKS: [(isize, isize); 4] = [(0, 0), (0, -1), (1, 0), (-1, 0)];
+
+// The kick test that let a rotation through.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Kick {
+    pub test: usize,
+    pub dx: isize,
+    pub dy: isize,
+}
+
+// The tests to try when rotating a block out of the given rotation state.
+pub fn kicks(kind: PieceKind, from: usize, clockwise: bool) -> &'static [(isize, isize)] {
+    let transition = if clockwise {
+        2 * from
+    } else {
+        (2 * from + 7) % 8
+    };
+
+    match kind {
+        PieceKind::I => &I_KICKS[transition],
+        PieceKind::O => &O_KICKS,
+        _ => &JLSTZ_KICKS[transition],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srs_kicks() {
+        let cases: Vec<(PieceKind, usize, bool, (isize, isize))> = vec![
+            (PieceKind::T, 0, true, (-1, -1)),
+            (PieceKind::J, 1, false, (1, 1)),
+            (PieceKind::S, 2, true, (1, -1)),
+            (PieceKind::Z, 0, false, (1, -1)),
+            (PieceKind::I, 0, true, (-2, 0)),
+            (PieceKind::I, 3, true, (1, 0)),
+            (PieceKind::I, 0, false, (-1, 0)),
+        ];
+
+        for case in cases {
+            let (kind, from, clockwise, want) = case;
+            let k = kicks(kind, from, clockwise);
+
+            assert_eq!(k.len(), KICK_TESTS);
+            assert_eq!(k[0], (0, 0));
+            assert!(k.contains(&want), "expected {:?} in {:?}", want, k);
+        }
+
+        assert_eq!(kicks(PieceKind::O, 0, true), &[(0, 0)]);
+    }
+}
@@ -0,0 +1,207 @@
+use super::gravity::FRAMES_PER_SECOND;
+use super::save::{SaveReader, SaveWriter};
+use super::{ClearKind, PieceKind, TSpin};
+
+// What the player has done over a game, to measure how well they're doing.
+pub struct Stats {
+    pieces: [usize; 7],
+    clears: [usize; 4],
+    tspins: usize,
+    inputs: usize,
+    max_combo: usize,
+    // The number of pieces dealt since the last I
+    drought: usize,
+    max_drought: usize,
+}
+
+fn piece_index(kind: PieceKind) -> usize {
+    PieceKind::ALL.iter().position(|k| *k == kind).unwrap()
+}
+
+fn clear_index(clear: ClearKind) -> usize {
+    ClearKind::ALL.iter().position(|c| *c == clear).unwrap()
+}
+
+fn per_second(count: usize, frames: u64) -> f64 {
+    if frames == 0 {
+        0.0
+    } else {
+        count as f64 * FRAMES_PER_SECOND as f64 / frames as f64
+    }
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            pieces: [0; 7],
+            clears: [0; 4],
+            tspins: 0,
+            inputs: 0,
+            max_combo: 0,
+            drought: 0,
+            max_drought: 0,
+        }
+    }
+
+    pub fn dealt(&mut self, kind: PieceKind) {
+        if kind == PieceKind::I {
+            self.drought = 0;
+        } else {
+            self.drought += 1;
+            self.max_drought = self.max_drought.max(self.drought);
+        }
+    }
+
+    pub fn input(&mut self, count: usize) {
+        self.inputs += count;
+    }
+
+    pub fn placed(&mut self, kind: PieceKind, clear: Option<ClearKind>, tspin: Option<TSpin>) {
+        self.pieces[piece_index(kind)] += 1;
+        if let Some(clear) = clear {
+            self.clears[clear_index(clear)] += 1;
+            if tspin.is_some() {
+                self.tspins += 1;
+            }
+        }
+    }
+
+    pub fn combo(&mut self, combo: usize) {
+        self.max_combo = self.max_combo.max(combo);
+    }
+
+    pub fn pieces(&self, kind: PieceKind) -> usize {
+        self.pieces[piece_index(kind)]
+    }
+
+    pub fn total_pieces(&self) -> usize {
+        self.pieces.iter().sum()
+    }
+
+    pub fn clears(&self, clear: ClearKind) -> usize {
+        self.clears[clear_index(clear)]
+    }
+
+    // The number of clears which were T-spins.
+    pub fn tspins(&self) -> usize {
+        self.tspins
+    }
+
+    pub fn max_combo(&self) -> usize {
+        self.max_combo
+    }
+
+    pub fn drought(&self) -> usize {
+        self.drought
+    }
+
+    pub fn max_drought(&self) -> usize {
+        self.max_drought
+    }
+
+    // Pieces per second, over the given number of frames.
+    pub fn pps(&self, frames: u64) -> f64 {
+        per_second(self.total_pieces(), frames)
+    }
+
+    // Actions per minute, over the given number of frames.
+    pub fn apm(&self, frames: u64) -> f64 {
+        per_second(self.inputs, frames) * 60.0
+    }
+
+    pub fn keys_per_piece(&self) -> f64 {
+        match self.total_pieces() {
+            0 => 0.0,
+            pieces => self.inputs as f64 / pieces as f64,
+        }
+    }
+
+    pub fn save(&self, w: &mut SaveWriter) {
+        w.list("stats_pieces", &self.pieces);
+        w.list("stats_clears", &self.clears);
+        w.field("stats_tspins", self.tspins);
+        w.field("stats_inputs", self.inputs);
+        w.field("stats_max_combo", self.max_combo);
+        w.field("stats_drought", self.drought);
+        w.field("stats_max_drought", self.max_drought);
+    }
+
+    pub fn load(r: &SaveReader) -> Result<Self, String> {
+        let mut stats = Self::new();
+
+        let pieces: Vec<usize> = r.list("stats_pieces")?;
+        let clears: Vec<usize> = r.list("stats_clears")?;
+        if pieces.len() != stats.pieces.len() || clears.len() != stats.clears.len() {
+            return Err("wrong number of stats in save".to_string());
+        }
+        stats.pieces.copy_from_slice(&pieces);
+        stats.clears.copy_from_slice(&clears);
+        stats.tspins = r.field("stats_tspins")?;
+        stats.inputs = r.field("stats_inputs")?;
+        stats.max_combo = r.field("stats_max_combo")?;
+        stats.drought = r.field("stats_drought")?;
+        stats.max_drought = r.field("stats_max_drought")?;
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_placed() {
+        let mut s = Stats::new();
+
+        s.placed(PieceKind::T, Some(ClearKind::Double), Some(TSpin::Full));
+        s.placed(PieceKind::I, Some(ClearKind::Tetris), None);
+        s.placed(PieceKind::I, None, None);
+
+        assert_eq!(s.pieces(PieceKind::I), 2);
+        assert_eq!(s.pieces(PieceKind::T), 1);
+        assert_eq!(s.pieces(PieceKind::O), 0);
+        assert_eq!(s.total_pieces(), 3);
+        assert_eq!(s.clears(ClearKind::Double), 1);
+        assert_eq!(s.clears(ClearKind::Tetris), 1);
+        assert_eq!(s.clears(ClearKind::Single), 0);
+        assert_eq!(s.tspins(), 1);
+    }
+
+    #[test]
+    fn stats_rates() {
+        let mut s = Stats::new();
+        assert_eq!(s.pps(0), 0.0);
+        assert_eq!(s.keys_per_piece(), 0.0);
+
+        for _ in 0..4 {
+            s.placed(PieceKind::O, None, None);
+        }
+        s.input(10);
+
+        // Over two seconds
+        let frames = 2 * FRAMES_PER_SECOND;
+        assert_eq!(s.pps(frames), 2.0);
+        assert_eq!(s.apm(frames), 300.0);
+        assert_eq!(s.keys_per_piece(), 2.5);
+    }
+
+    #[test]
+    fn stats_drought() {
+        let mut s = Stats::new();
+
+        let dealt = [
+            PieceKind::O,
+            PieceKind::T,
+            PieceKind::S,
+            PieceKind::I,
+            PieceKind::Z,
+        ];
+        for kind in dealt.iter() {
+            s.dealt(*kind);
+        }
+
+        assert_eq!(s.drought(), 1);
+        assert_eq!(s.max_drought(), 3);
+    }
+}